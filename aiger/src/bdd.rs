@@ -0,0 +1,308 @@
+use crate::{Aig, And, Latch};
+use bdds::{Bdd, BddManager, BddNodes};
+use fsmbdd::{FsmBdd, TransBddMethod};
+use std::{
+    collections::HashMap,
+    ops::{BitAnd, BitOr, BitXor, Not},
+};
+
+/// BDDs of an AIGER circuit. Inputs and latches get a current/next variable pair each, in this
+/// order, inputs are left unconstrained by the transition.
+#[derive(Clone)]
+pub struct AigBdd<BM: BddManager>
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    pub manager: BM,
+    pub symbols: HashMap<String, usize>,
+    pub trans: Vec<BM::Bdd>,
+    pub init: BM::Bdd,
+    pub invariants: BM::Bdd,
+    pub bads: Vec<BM::Bdd>,
+    pub justice: Vec<Vec<BM::Bdd>>,
+    pub fairness: Vec<BM::Bdd>,
+}
+
+struct AigToBdd<'m, BM: BddManager>
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    ands: HashMap<usize, And>,
+    bdds: Vec<Option<BM::Bdd>>,
+    manager: &'m BM,
+}
+
+impl<BM: BddManager> AigToBdd<'_, BM>
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    /// BDD of `lit`, the and gates below it are built bottom up without recursion since the
+    /// ASCII format does not require them to be sorted.
+    fn lit_bdd(&mut self, lit: usize) -> BM::Bdd {
+        let mut stack = vec![lit / 2];
+        while let Some(var) = stack.last().copied() {
+            if self.bdds[var].is_some() {
+                stack.pop();
+                continue;
+            }
+            let and = self.ands[&var];
+            let pending: Vec<usize> = [and.rhs0 / 2, and.rhs1 / 2]
+                .into_iter()
+                .filter(|var| self.bdds[*var].is_none())
+                .collect();
+            if pending.is_empty() {
+                let bdd = self.cached(and.rhs0) & self.cached(and.rhs1);
+                self.bdds[var] = Some(bdd);
+                stack.pop();
+            } else {
+                stack.extend(pending);
+            }
+        }
+        self.cached(lit)
+    }
+
+    fn cached(&self, lit: usize) -> BM::Bdd {
+        let bdd = self.bdds[lit / 2].clone().unwrap();
+        if lit & 1 == 1 {
+            !bdd
+        } else {
+            bdd
+        }
+    }
+
+    fn lits_bdd(&mut self, lits: &[usize]) -> Vec<BM::Bdd> {
+        lits.iter().map(|lit| self.lit_bdd(*lit)).collect()
+    }
+
+    fn conjunction(&mut self, lits: &[usize]) -> BM::Bdd {
+        let mut res = self.manager.constant(true);
+        for lit in lits {
+            res &= self.lit_bdd(*lit);
+        }
+        res
+    }
+}
+
+impl<BM: BddManager> AigBdd<BM>
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    pub fn new(manager: &BM, aig: &Aig) -> Self {
+        let mut builder = AigToBdd {
+            ands: aig.ands.iter().map(|and| (and.lhs / 2, *and)).collect(),
+            bdds: vec![None; aig.max_var + 1],
+            manager,
+        };
+        builder.bdds[0] = Some(manager.constant(false));
+        let mut symbols = HashMap::new();
+        let vars = aig
+            .inputs
+            .iter()
+            .chain(aig.latches.iter().map(|latch| &latch.lit));
+        for (i, lit) in vars.enumerate() {
+            let current = i * 2;
+            let next = current + 1;
+            builder.bdds[lit / 2] = Some(manager.ith_var(current));
+            manager.ith_var(next);
+            let mut name = aig.symbol(*lit);
+            if symbols.contains_key(&name) {
+                name = format!("{}_{}", name, lit / 2);
+            }
+            assert!(symbols.insert(name, current).is_none());
+        }
+        let mut init = manager.constant(true);
+        let mut trans = vec![];
+        for latch in aig.latches.iter() {
+            let current = builder.lit_bdd(latch.lit);
+            match latch.reset {
+                Some(true) => init &= &current,
+                Some(false) => init &= !&current,
+                None => (),
+            }
+            let next = builder.lit_bdd(latch.next);
+            trans.push(!(current.next_state() ^ next));
+        }
+        let invariants = builder.conjunction(&aig.constraints);
+        let bads = builder.lits_bdd(&aig.bads);
+        let justice = aig
+            .justice
+            .iter()
+            .map(|justice| builder.lits_bdd(justice))
+            .collect();
+        let fairness = builder.lits_bdd(&aig.fairness);
+        Self {
+            manager: manager.clone(),
+            symbols,
+            trans,
+            init,
+            invariants,
+            bads,
+            justice,
+            fairness,
+        }
+    }
+
+    /// The circuit as an `FsmBdd` whose justice holds the fairness constraints and, if any, the
+    /// first justice property.
+    pub fn to_fsmbdd(&self, method: TransBddMethod, cluster_threshold: usize) -> FsmBdd<BM> {
+        let trans = fsmbdd::Trans::new_with_threshold(
+            &self.manager,
+            self.trans.clone(),
+            method,
+            cluster_threshold,
+        );
+        let mut justice = self.fairness.clone();
+        if let Some(property) = self.justice.first() {
+            justice.extend(property.iter().cloned());
+        }
+        FsmBdd {
+            symbols: self.symbols.clone(),
+            manager: self.manager.clone(),
+            init: self.init.clone(),
+            invariants: self.invariants.clone(),
+            trans,
+            justice,
+        }
+    }
+}
+
+/// Builds and gates with structural hashing, so BDD nodes shared between the exported BDDs
+/// become shared gates.
+#[derive(Default)]
+struct AigBuilder {
+    aig: Aig,
+    strash: HashMap<(usize, usize), usize>,
+}
+
+impl AigBuilder {
+    fn and(&mut self, x: usize, y: usize) -> usize {
+        let (rhs0, rhs1) = if x > y { (x, y) } else { (y, x) };
+        if rhs1 == 0 || rhs0 == rhs1 ^ 1 {
+            return 0;
+        }
+        if rhs1 == 1 || rhs0 == rhs1 {
+            return rhs0;
+        }
+        if let Some(lhs) = self.strash.get(&(rhs0, rhs1)) {
+            return *lhs;
+        }
+        self.aig.max_var += 1;
+        let lhs = 2 * self.aig.max_var;
+        self.aig.ands.push(And { lhs, rhs0, rhs1 });
+        self.strash.insert((rhs0, rhs1), lhs);
+        lhs
+    }
+
+    fn or(&mut self, x: usize, y: usize) -> usize {
+        self.and(x ^ 1, y ^ 1) ^ 1
+    }
+
+    /// Every BDD node becomes a multiplexer on the literal of its variable.
+    fn bdd(&mut self, nodes: BddNodes, var_lits: &HashMap<usize, usize>) -> usize {
+        let mut lits = vec![0, 1];
+        for (var, low, high) in nodes.nodes {
+            let x = var_lits[&var];
+            let high = self.and(x, lits[high]);
+            let low = self.and(x ^ 1, lits[low]);
+            let lit = self.or(high, low);
+            lits.push(lit);
+        }
+        lits[nodes.root]
+    }
+}
+
+impl Aig {
+    /// Encodes `fsmbdd` as a circuit. Every state variable is a latch whose next value is a
+    /// fresh input standing for its next state variable, the transition partitions, the
+    /// invariants and the initial states are constraints over both. The initial states are
+    /// only constrained while the extra last latch, set at reset, is on. The justice sets form
+    /// the single justice property, a fair path of the model is a witness of it.
+    pub fn from_fsmbdd<BM: BddManager>(fsmbdd: &FsmBdd<BM>) -> Self
+    where
+        for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+            + BitAnd<BM::Bdd, Output = BM::Bdd>
+            + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+            + BitOr<BM::Bdd, Output = BM::Bdd>
+            + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+            + BitXor<BM::Bdd, Output = BM::Bdd>
+            + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+    {
+        let state_vars = fsmbdd.manager.state_vars();
+        let num_state = state_vars.len();
+        let names: HashMap<usize, &String> = fsmbdd
+            .symbols
+            .iter()
+            .map(|(name, var)| (*var, name))
+            .collect();
+        let mut builder = AigBuilder::default();
+        let mut var_lits = HashMap::new();
+        for (i, var) in state_vars.iter().enumerate() {
+            let input = 2 * (i + 1);
+            let latch = 2 * (num_state + i + 1);
+            var_lits.insert(*var, latch);
+            var_lits.insert(var + 1, input);
+            let name = match names.get(var) {
+                Some(name) => name.to_string(),
+                None => format!("v{}", var),
+            };
+            builder.aig.inputs.push(input);
+            builder.aig.latches.push(Latch {
+                lit: latch,
+                next: input,
+                reset: None,
+            });
+            builder.aig.symbols.insert(input, format!("next({})", name));
+            builder.aig.symbols.insert(latch, name);
+        }
+        let first = 2 * (2 * num_state + 1);
+        builder.aig.latches.push(Latch {
+            lit: first,
+            next: 0,
+            reset: Some(true),
+        });
+        builder.aig.symbols.insert(first, "first".to_string());
+        builder.aig.max_var = 2 * num_state + 1;
+        let init = builder.bdd(fsmbdd.init.export(), &var_lits);
+        let init = builder.or(first ^ 1, init);
+        let mut constraints = vec![init];
+        constraints.push(builder.bdd(fsmbdd.invariants.export(), &var_lits));
+        for partition in fsmbdd.trans.partitions() {
+            constraints.push(builder.bdd(partition.export(), &var_lits));
+        }
+        let mut justice: Vec<usize> = fsmbdd
+            .justice
+            .iter()
+            .map(|justice| builder.bdd(justice.export(), &var_lits))
+            .collect();
+        if justice.is_empty() {
+            justice.push(1);
+        }
+        builder.aig.constraints = constraints;
+        builder.aig.justice = vec![justice];
+        builder.aig
+    }
+}
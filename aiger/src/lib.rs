@@ -0,0 +1,290 @@
+pub mod bdd;
+mod write;
+
+use std::{collections::HashMap, fs::read, io, path::Path};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Latch {
+    pub lit: usize,
+    pub next: usize,
+    /// `None` for an uninitialized latch.
+    pub reset: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct And {
+    pub lhs: usize,
+    pub rhs0: usize,
+    pub rhs1: usize,
+}
+
+/// An AIGER 1.9 circuit. Literals are `2 * var + sign`, literal 0 is false and 1 is true.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Aig {
+    pub max_var: usize,
+    pub inputs: Vec<usize>,
+    pub latches: Vec<Latch>,
+    pub outputs: Vec<usize>,
+    pub bads: Vec<usize>,
+    pub constraints: Vec<usize>,
+    pub justice: Vec<Vec<usize>>,
+    pub fairness: Vec<usize>,
+    pub ands: Vec<And>,
+    pub symbols: HashMap<usize, String>,
+}
+
+struct Reader<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    fn line(&mut self) -> &str {
+        let start = self.pos;
+        while self.pos < self.input.len() && self.input[self.pos] != b'\n' {
+            self.pos += 1;
+        }
+        let line = &self.input[start..self.pos];
+        self.pos += 1;
+        std::str::from_utf8(line).unwrap()
+    }
+
+    fn numbers(&mut self) -> Vec<usize> {
+        self.line()
+            .split_whitespace()
+            .map(|number| number.parse().unwrap())
+            .collect()
+    }
+
+    fn number(&mut self) -> usize {
+        let numbers = self.numbers();
+        assert!(numbers.len() == 1, "expected one literal per line");
+        numbers[0]
+    }
+
+    /// A 7-bit little endian delta of the binary and section.
+    fn delta(&mut self) -> usize {
+        let mut res = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.input[self.pos];
+            self.pos += 1;
+            res |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                break res;
+            }
+            shift += 7;
+        }
+    }
+
+    fn is_end(&self) -> bool {
+        self.pos >= self.input.len()
+    }
+}
+
+impl Aig {
+    fn parse_latch(&self, lit: usize, numbers: &[usize]) -> Latch {
+        let reset = match numbers.get(1) {
+            None | Some(0) => Some(false),
+            Some(1) => Some(true),
+            Some(reset) if *reset == lit => None,
+            Some(reset) => panic!("unsupported latch reset {}", reset),
+        };
+        Latch {
+            lit,
+            next: numbers[0],
+            reset,
+        }
+    }
+
+    /// Parses both the ASCII `aag` and the binary `aig` format.
+    pub fn parse(input: &[u8]) -> Self {
+        let mut reader = Reader { input, pos: 0 };
+        let header = reader.line().to_string();
+        let mut header = header.split_whitespace();
+        let binary = match header.next() {
+            Some("aag") => false,
+            Some("aig") => true,
+            _ => panic!("not an aiger file"),
+        };
+        let mut counts: Vec<usize> = header.map(|count| count.parse().unwrap()).collect();
+        assert!(counts.len() >= 5, "incomplete aiger header");
+        counts.resize(9, 0);
+        let [max_var, num_input, num_latch, num_output, num_and, num_bad, num_constraint, num_justice, num_fairness] =
+            counts[..]
+        else {
+            unreachable!()
+        };
+        let mut aig = Aig {
+            max_var,
+            ..Default::default()
+        };
+        for i in 0..num_input {
+            let lit = if binary { 2 * (i + 1) } else { reader.number() };
+            aig.inputs.push(lit);
+        }
+        for i in 0..num_latch {
+            let mut numbers = reader.numbers();
+            let lit = if binary {
+                2 * (num_input + i + 1)
+            } else {
+                numbers.remove(0)
+            };
+            let latch = aig.parse_latch(lit, &numbers);
+            aig.latches.push(latch);
+        }
+        for _ in 0..num_output {
+            aig.outputs.push(reader.number());
+        }
+        for _ in 0..num_bad {
+            aig.bads.push(reader.number());
+        }
+        for _ in 0..num_constraint {
+            aig.constraints.push(reader.number());
+        }
+        let justice_sizes: Vec<usize> = (0..num_justice).map(|_| reader.number()).collect();
+        for size in justice_sizes {
+            let justice = (0..size).map(|_| reader.number()).collect();
+            aig.justice.push(justice);
+        }
+        for _ in 0..num_fairness {
+            aig.fairness.push(reader.number());
+        }
+        for i in 0..num_and {
+            let and = if binary {
+                let lhs = 2 * (num_input + num_latch + i + 1);
+                let rhs0 = lhs - reader.delta();
+                let rhs1 = rhs0 - reader.delta();
+                And { lhs, rhs0, rhs1 }
+            } else {
+                let numbers = reader.numbers();
+                And {
+                    lhs: numbers[0],
+                    rhs0: numbers[1],
+                    rhs1: numbers[2],
+                }
+            };
+            aig.ands.push(and);
+        }
+        while !reader.is_end() {
+            let line = reader.line();
+            if line.starts_with('c') {
+                break;
+            }
+            let Some((position, name)) = line.split_once(' ') else {
+                continue;
+            };
+            let index: usize = position[1..].parse().unwrap();
+            let lit = match &position[..1] {
+                "i" => aig.inputs[index],
+                "l" => aig.latches[index].lit,
+                _ => continue,
+            };
+            aig.symbols.insert(lit, name.to_string());
+        }
+        aig
+    }
+
+    pub fn from_file<P: AsRef<Path>>(file: P) -> io::Result<Self> {
+        let input = read(file)?;
+        Ok(Self::parse(&input))
+    }
+
+    /// Name of the input or latch `lit`, from the symbol table if present.
+    pub fn symbol(&self, lit: usize) -> String {
+        if let Some(name) = self.symbols.get(&lit) {
+            return name.clone();
+        }
+        if let Some(i) = self.inputs.iter().position(|input| *input == lit) {
+            return format!("i{}", i);
+        }
+        let i = self
+            .latches
+            .iter()
+            .position(|latch| latch.lit == lit)
+            .unwrap();
+        format!("l{}", i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{bdd::AigBdd, Aig, Latch};
+    use fsmbdd::TransBddMethod;
+    use peabody::Peabody;
+
+    /// A latch set by an input once it is clear, bad once the latch is set.
+    const SET_AAG: &str = "aag 3 1 1 0 1 1 0 1 0
+2
+4 6
+4
+1
+4
+6 5 2
+i0 enable
+l0 q
+c
+set
+";
+
+    #[test]
+    fn test_parse_ascii_and_binary() {
+        let aag = Aig::parse(SET_AAG.as_bytes());
+        assert_eq!(aag.inputs, vec![2]);
+        assert_eq!(
+            aag.latches,
+            vec![Latch {
+                lit: 4,
+                next: 6,
+                reset: Some(false)
+            }]
+        );
+        assert_eq!(aag.bads, vec![4]);
+        assert_eq!(aag.justice, vec![vec![4]]);
+        assert_eq!(aag.symbol(2), "enable");
+        assert_eq!(aag.symbol(4), "q");
+        let mut aig = b"aig 3 1 1 0 1 1 0 1 0\n6\n4\n1\n4\n".to_vec();
+        aig.extend([1, 3]);
+        aig.extend(b"i0 enable\nl0 q\nc\nset\n");
+        let aig = Aig::parse(&aig);
+        assert_eq!(aig, aag);
+    }
+
+    #[test]
+    fn test_to_fsmbdd() {
+        let aig = Aig::parse(SET_AAG.as_bytes());
+        let manager = Peabody::new();
+        let aigbdd = AigBdd::new(&manager, &aig);
+        let fsmbdd = aigbdd.to_fsmbdd(TransBddMethod::Partition, 1000);
+        let q = manager.ith_var(aigbdd.symbols["q"]);
+        assert_eq!(fsmbdd.init, !&q);
+        let reach = fsmbdd.reachable_from_init();
+        assert!(!(&reach & &aigbdd.bads[0]).is_constant(false));
+        // `q` is cleared right after it is set, so it can be set infinitely often
+        assert!(!fsmbdd.fair_cycle_with_constrain(&reach).is_constant(false));
+        assert_eq!(fsmbdd.k_liveness(&reach), None);
+    }
+
+    #[test]
+    fn test_write() {
+        let aag = Aig::parse(SET_AAG.as_bytes());
+        assert_eq!(Aig::parse(aag.write_ascii().as_bytes()), aag);
+        assert_eq!(Aig::parse(&aag.write_binary()), aag);
+        let manager = Peabody::new();
+        let fsmbdd = AigBdd::new(&manager, &aag).to_fsmbdd(TransBddMethod::Partition, 1000);
+        let exported = Aig::from_fsmbdd(&fsmbdd);
+        assert_eq!(Aig::parse(&exported.write_binary()), exported);
+        assert!(exported.write_blif("set").contains(".latch"));
+        // the exported circuit has the same fair paths
+        let manager = Peabody::new();
+        let fsmbdd = AigBdd::new(&manager, &exported).to_fsmbdd(TransBddMethod::Partition, 1000);
+        assert_eq!(fsmbdd.k_liveness(&fsmbdd.reachable_from_init()), None);
+        let mut stuck = aag.clone();
+        stuck.ands[0].rhs0 = 4;
+        let fsmbdd = AigBdd::new(&manager, &stuck).to_fsmbdd(TransBddMethod::Partition, 1000);
+        let exported = Aig::from_fsmbdd(&fsmbdd);
+        let manager = Peabody::new();
+        let fsmbdd = AigBdd::new(&manager, &exported).to_fsmbdd(TransBddMethod::Partition, 1000);
+        assert!(fsmbdd.k_liveness(&fsmbdd.reachable_from_init()).is_some());
+    }
+}
@@ -0,0 +1,200 @@
+use crate::Aig;
+use std::{fs::write, io, path::Path};
+
+impl Aig {
+    fn header(&self, format: &str) -> String {
+        let mut counts = vec![
+            self.max_var,
+            self.inputs.len(),
+            self.latches.len(),
+            self.outputs.len(),
+            self.ands.len(),
+        ];
+        let extra = [
+            self.bads.len(),
+            self.constraints.len(),
+            self.justice.len(),
+            self.fairness.len(),
+        ];
+        if extra.iter().any(|count| *count > 0) {
+            counts.extend(extra);
+        }
+        let counts: Vec<String> = counts.iter().map(|count| count.to_string()).collect();
+        format!("{} {}\n", format, counts.join(" "))
+    }
+
+    /// Everything after the latches up to the and gates, shared by both formats.
+    fn properties(&self) -> String {
+        let mut res = String::new();
+        for lit in self
+            .outputs
+            .iter()
+            .chain(self.bads.iter())
+            .chain(self.constraints.iter())
+        {
+            res.push_str(&format!("{}\n", lit));
+        }
+        for justice in self.justice.iter() {
+            res.push_str(&format!("{}\n", justice.len()));
+        }
+        for lit in self.justice.iter().flatten().chain(self.fairness.iter()) {
+            res.push_str(&format!("{}\n", lit));
+        }
+        res
+    }
+
+    fn latch_line(&self, i: usize, binary: bool) -> String {
+        let latch = &self.latches[i];
+        let mut line = if binary {
+            latch.next.to_string()
+        } else {
+            format!("{} {}", latch.lit, latch.next)
+        };
+        match latch.reset {
+            Some(false) => (),
+            Some(true) => line.push_str(" 1"),
+            None => line.push_str(&format!(" {}", latch.lit)),
+        }
+        line.push('\n');
+        line
+    }
+
+    fn symbol_table(&self) -> String {
+        let mut res = String::new();
+        for (i, input) in self.inputs.iter().enumerate() {
+            if let Some(name) = self.symbols.get(input) {
+                res.push_str(&format!("i{} {}\n", i, name));
+            }
+        }
+        for (i, latch) in self.latches.iter().enumerate() {
+            if let Some(name) = self.symbols.get(&latch.lit) {
+                res.push_str(&format!("l{} {}\n", i, name));
+            }
+        }
+        res
+    }
+
+    pub fn write_ascii(&self) -> String {
+        let mut res = self.header("aag");
+        for input in self.inputs.iter() {
+            res.push_str(&format!("{}\n", input));
+        }
+        for i in 0..self.latches.len() {
+            res.push_str(&self.latch_line(i, false));
+        }
+        res.push_str(&self.properties());
+        for and in self.ands.iter() {
+            res.push_str(&format!("{} {} {}\n", and.lhs, and.rhs0, and.rhs1));
+        }
+        res.push_str(&self.symbol_table());
+        res
+    }
+
+    /// The binary format, inputs, latches and and gates must be numbered in this order and every
+    /// gate may only refer to smaller literals.
+    pub fn write_binary(&self) -> Vec<u8> {
+        let num_input = self.inputs.len();
+        let num_latch = self.latches.len();
+        let mut res = self.header("aig");
+        for (i, input) in self.inputs.iter().enumerate() {
+            assert!(*input == 2 * (i + 1), "inputs are not in binary order");
+        }
+        for i in 0..num_latch {
+            assert!(self.latches[i].lit == 2 * (num_input + i + 1));
+            res.push_str(&self.latch_line(i, true));
+        }
+        res.push_str(&self.properties());
+        let mut res = res.into_bytes();
+        for (i, and) in self.ands.iter().enumerate() {
+            assert!(and.lhs == 2 * (num_input + num_latch + i + 1));
+            assert!(and.lhs > and.rhs0 && and.rhs0 >= and.rhs1);
+            for mut delta in [and.lhs - and.rhs0, and.rhs0 - and.rhs1] {
+                while delta >= 0x80 {
+                    res.push((delta & 0x7f) as u8 | 0x80);
+                    delta >>= 7;
+                }
+                res.push(delta as u8);
+            }
+        }
+        res.extend(self.symbol_table().into_bytes());
+        res
+    }
+
+    /// BLIF has neither constraints nor justice, they become the outputs `constraint<i>` and
+    /// `justice<i>_<j>`. Signal `n<v>` is the AIGER variable `v`.
+    pub fn write_blif(&self, model: &str) -> String {
+        let signal = |lit: usize| format!("n{}", lit / 2);
+        let mut outputs = Vec::new();
+        for (i, lit) in self.outputs.iter().enumerate() {
+            outputs.push((format!("o{}", i), *lit));
+        }
+        for (i, lit) in self.bads.iter().enumerate() {
+            outputs.push((format!("bad{}", i), *lit));
+        }
+        for (i, lit) in self.constraints.iter().enumerate() {
+            outputs.push((format!("constraint{}", i), *lit));
+        }
+        for (i, justice) in self.justice.iter().enumerate() {
+            for (j, lit) in justice.iter().enumerate() {
+                outputs.push((format!("justice{}_{}", i, j), *lit));
+            }
+        }
+        for (i, lit) in self.fairness.iter().enumerate() {
+            outputs.push((format!("fairness{}", i), *lit));
+        }
+        let inputs: Vec<String> = self.inputs.iter().map(|lit| signal(*lit)).collect();
+        let names: Vec<&str> = outputs.iter().map(|(name, _)| name.as_str()).collect();
+        let mut res = format!(".model {}\n", model);
+        res.push_str(&format!(".inputs {}\n", inputs.join(" ")));
+        res.push_str(&format!(".outputs {}\n", names.join(" ")));
+        // the constant false signal
+        res.push_str(".names n0\n");
+        let buffer = |lit: usize, name: &str| {
+            let value = if lit & 1 == 1 { 0 } else { 1 };
+            format!(".names {} {}\n{} 1\n", signal(lit), name, value)
+        };
+        for latch in self.latches.iter() {
+            let next = format!("{}_next", signal(latch.lit));
+            res.push_str(&buffer(latch.next, &next));
+            let reset = match latch.reset {
+                Some(reset) => reset as usize,
+                None => 3,
+            };
+            res.push_str(&format!(
+                ".latch {} {} {}\n",
+                next,
+                signal(latch.lit),
+                reset
+            ));
+        }
+        for and in self.ands.iter() {
+            res.push_str(&format!(
+                ".names {} {} {}\n{}{} 1\n",
+                signal(and.rhs0),
+                signal(and.rhs1),
+                signal(and.lhs),
+                1 - and.rhs0 % 2,
+                1 - and.rhs1 % 2
+            ));
+        }
+        for (name, lit) in outputs.iter() {
+            res.push_str(&buffer(*lit, name));
+        }
+        res.push_str(".end\n");
+        res
+    }
+
+    /// Writes the format given by the extension of `file`, `aag`, `aig` or `blif`.
+    pub fn write_file<P: AsRef<Path>>(&self, file: P) -> io::Result<()> {
+        let file = file.as_ref();
+        match file.extension().and_then(|extension| extension.to_str()) {
+            Some("aag") => write(file, self.write_ascii()),
+            Some("aig") => write(file, self.write_binary()),
+            Some("blif") => {
+                let model = file.file_stem().unwrap().to_string_lossy();
+                write(file, self.write_blif(&model))
+            }
+            _ => panic!("unknown circuit format of {}", file.display()),
+        }
+    }
+}
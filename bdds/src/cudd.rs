@@ -1,4 +1,4 @@
-use crate::{Bdd, BddManager};
+use crate::{Bdd, BddManager, BddNodes};
 use cudd::Cudd;
 
 impl Bdd for cudd::Bdd {
@@ -41,17 +41,24 @@ impl Bdd for cudd::Bdd {
     fn support_index(&self) -> Vec<usize> {
         self.support_index()
     }
+
+    fn export(&self) -> BddNodes {
+        let (nodes, root) = self.export();
+        BddNodes { nodes, root }
+    }
 }
 
 impl BddManager for Cudd {
     type Bdd = cudd::Bdd;
 
+    type Detached = BddNodes;
+
     fn new() -> Self {
         Self::new()
     }
 
     fn new_with_capacity(_capacity: usize) -> Self {
-        todo!()
+        Self::new()
     }
 
     fn constant(&self, val: bool) -> Self::Bdd {
@@ -69,4 +76,40 @@ impl BddManager for Cudd {
     fn translocate(&self, bdd: &Self::Bdd) -> Self::Bdd {
         self.translocate(bdd)
     }
+
+    fn detach(&self, bdd: &Self::Bdd) -> Self::Detached {
+        Bdd::export(bdd)
+    }
+
+    fn attach(&self, bdd: &Self::Detached) -> Self::Bdd {
+        self.import(bdd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BddManager;
+    use cudd::Cudd;
+
+    #[test]
+    fn test_cudd() {
+        let manager = <Cudd as BddManager>::new();
+        let x = manager.ith_var(0);
+        let y = manager.ith_var(2);
+        let xy = &x & &y;
+        assert!(!xy.is_constant(false));
+        assert!((&xy & !&x).is_constant(false));
+        assert_eq!(xy.support_index(), vec![0, 2]);
+        assert_eq!(xy.next_state().support_index(), vec![1, 3]);
+        assert_eq!(xy.next_state().previous_state(), xy);
+        // the next `x` is the current `y` and the next `y` the current `x`
+        let xp = manager.ith_var(1);
+        let yp = manager.ith_var(3);
+        let trans = !(&xp ^ &y) & !(&yp ^ &x);
+        assert_eq!(x.post_image(&trans), y);
+        assert_eq!(y.pre_image(&trans), x);
+        let other = <Cudd as BddManager>::new();
+        let moved = other.attach(&manager.detach(&(&x | !&y)));
+        assert_eq!(manager.attach(&other.detach(&moved)), &x | !&y);
+    }
 }
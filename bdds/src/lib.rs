@@ -1,5 +1,8 @@
-// mod cudd;
+#[cfg(feature = "cudd")]
+mod cudd;
+#[cfg(feature = "peabody")]
 mod peabody;
+#[cfg(feature = "sylvan")]
 mod sylvan;
 
 use std::{
@@ -9,6 +12,8 @@ use std::{
 
 pub trait Bdd:
     Sized
+    + Send
+    + Sync
     + PartialEq
     + Eq
     + Clone
@@ -55,9 +60,21 @@ where
     fn support(&self) -> Self;
 
     fn support_index(&self) -> Vec<usize>;
+
+    fn export(&self) -> BddNodes;
+}
+
+/// A BDD independent of any manager, it can be rebuilt in another manager with
+/// [`BddManager::import`]. References `0` and `1` are the constants `false` and `true`,
+/// reference `i + 2` is `nodes[i]`. Every node `(var, low, high)` only refers to the constants
+/// and to the nodes before it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BddNodes {
+    pub nodes: Vec<(usize, usize, usize)>,
+    pub root: usize,
 }
 
-pub trait BddManager: Sized + Clone + Debug + 'static + PartialEq
+pub trait BddManager: Sized + Clone + Debug + Send + Sync + 'static + PartialEq
 where
     for<'a, 'b> &'a Self::Bdd: Not<Output = Self::Bdd>
         + BitAnd<Self::Bdd, Output = Self::Bdd>
@@ -69,6 +86,12 @@ where
 {
     type Bdd: Bdd;
 
+    type Detached: Send + Sync + Debug;
+
+    /// Whether every manager works on one node table, then a manager of its own isolates
+    /// nothing.
+    const SHARED_TABLE: bool = false;
+
     fn new() -> Self;
 
     fn new_with_capacity(capacity: usize) -> Self;
@@ -88,4 +111,25 @@ where
     }
 
     fn translocate(&self, bdd: &Self::Bdd) -> Self::Bdd;
+
+    fn import(&self, bdd: &BddNodes) -> Self::Bdd {
+        let mut nodes = vec![self.constant(false), self.constant(true)];
+        for (var, low, high) in bdd.nodes.iter() {
+            let node = self.ith_var(*var).if_then_else(&nodes[*high], &nodes[*low]);
+            nodes.push(node);
+        }
+        nodes.swap_remove(bdd.root)
+    }
+
+    /// Converts `bdd` into a form that can be moved to a thread working on another manager.
+    /// Managers that are not thread-safe must not be touched from that thread, so they
+    /// export the BDD here, the others can pass it on as is.
+    ///
+    /// Only Peabody and CUDD give each manager a node table of its own. Sylvan has a single
+    /// global table, so its managers all share it and detaching, attaching and translocating
+    /// are plain clones that isolate nothing.
+    fn detach(&self, bdd: &Self::Bdd) -> Self::Detached;
+
+    /// Rebuilds a BDD detached from any manager (possibly this one) in this manager.
+    fn attach(&self, bdd: &Self::Detached) -> Self::Bdd;
 }
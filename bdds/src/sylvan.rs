@@ -1,4 +1,4 @@
-use crate::{Bdd, BddManager};
+use crate::{Bdd, BddManager, BddNodes};
 
 impl Bdd for sylvan::Bdd {
     fn size(&self) -> usize {
@@ -40,11 +40,22 @@ impl Bdd for sylvan::Bdd {
     fn support_index(&self) -> Vec<usize> {
         self.support_index()
     }
+
+    fn export(&self) -> BddNodes {
+        let (nodes, root) = self.export();
+        BddNodes { nodes, root }
+    }
 }
 
 impl BddManager for sylvan::Sylvan {
     type Bdd = sylvan::Bdd;
 
+    type Detached = sylvan::Bdd;
+
+    const SHARED_TABLE: bool = true;
+
+    // Sylvan has a single global node table, which is thread-safe, so all managers share it
+    // and BDDs never need to be moved between them.
     fn new() -> Self {
         Self::new()
     }
@@ -65,7 +76,16 @@ impl BddManager for sylvan::Sylvan {
         Self::num_var()
     }
 
+    // every manager works on the one global table of Sylvan
     fn translocate(&self, bdd: &Self::Bdd) -> Self::Bdd {
         bdd.clone()
     }
+
+    fn detach(&self, bdd: &Self::Bdd) -> Self::Detached {
+        bdd.clone()
+    }
+
+    fn attach(&self, bdd: &Self::Detached) -> Self::Bdd {
+        bdd.clone()
+    }
 }
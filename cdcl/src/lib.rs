@@ -0,0 +1,545 @@
+use logic_form::{Cnf, Lit, Var};
+use std::mem::take;
+
+const RESTART_FIRST: usize = 100;
+const RESTART_INC: f64 = 1.5;
+const VAR_DECAY: f64 = 0.95;
+const SIMPLIFY_INTERVAL: usize = 1000;
+
+fn index(lit: Lit) -> usize {
+    usize::from(lit)
+}
+
+fn lit_value(value: &[Option<bool>], lit: Lit) -> Option<bool> {
+    value[usize::from(lit.var())].map(|v| v != lit.compl())
+}
+
+/// Binary max-heap of variables by activity, `position` makes membership and updates cheap.
+#[derive(Default)]
+struct VarOrder {
+    activity: Vec<f64>,
+    heap: Vec<usize>,
+    position: Vec<Option<usize>>,
+}
+
+impl VarOrder {
+    fn new_var(&mut self) {
+        self.activity.push(0.0);
+        self.position.push(None);
+        self.insert(self.activity.len() - 1);
+    }
+
+    fn insert(&mut self, var: usize) {
+        if self.position[var].is_some() {
+            return;
+        }
+        self.heap.push(var);
+        self.up(self.heap.len() - 1);
+    }
+
+    fn pop(&mut self) -> Option<usize> {
+        let last = self.heap.pop()?;
+        if self.heap.is_empty() {
+            self.position[last] = None;
+            return Some(last);
+        }
+        let top = self.heap[0];
+        self.position[top] = None;
+        self.heap[0] = last;
+        self.down(0);
+        Some(top)
+    }
+
+    fn bump(&mut self, var: usize, inc: f64) -> bool {
+        self.activity[var] += inc;
+        if let Some(i) = self.position[var] {
+            self.up(i);
+        }
+        if self.activity[var] > 1e100 {
+            for activity in self.activity.iter_mut() {
+                *activity *= 1e-100;
+            }
+            return true;
+        }
+        false
+    }
+
+    fn up(&mut self, mut i: usize) {
+        let var = self.heap[i];
+        while i > 0 {
+            let parent = (i - 1) / 2;
+            if self.activity[self.heap[parent]] >= self.activity[var] {
+                break;
+            }
+            self.heap[i] = self.heap[parent];
+            self.position[self.heap[i]] = Some(i);
+            i = parent;
+        }
+        self.heap[i] = var;
+        self.position[var] = Some(i);
+    }
+
+    fn down(&mut self, mut i: usize) {
+        let var = self.heap[i];
+        loop {
+            let left = 2 * i + 1;
+            if left >= self.heap.len() {
+                break;
+            }
+            let right = left + 1;
+            let child = if right < self.heap.len()
+                && self.activity[self.heap[right]] > self.activity[self.heap[left]]
+            {
+                right
+            } else {
+                left
+            };
+            if self.activity[self.heap[child]] <= self.activity[var] {
+                break;
+            }
+            self.heap[i] = self.heap[child];
+            self.position[self.heap[i]] = Some(i);
+            i = child;
+        }
+        self.heap[i] = var;
+        self.position[var] = Some(i);
+    }
+}
+
+/// A CDCL SAT solver with two watched literals, first-UIP learning, VSIDS decisions, phase
+/// saving and geometric restarts. Var 0 is the constant, `Lit::constant_lit(b)` has value `b`.
+pub struct Solver {
+    clauses: Vec<Vec<Lit>>,
+    watches: Vec<Vec<usize>>,
+    value: Vec<Option<bool>>,
+    level: Vec<usize>,
+    reason: Vec<Option<usize>>,
+    phase: Vec<bool>,
+    var_inc: f64,
+    order: VarOrder,
+    trail: Vec<Lit>,
+    trail_lim: Vec<usize>,
+    qhead: usize,
+    unsat: bool,
+    model: Vec<Option<bool>>,
+    failed: Vec<Lit>,
+    simplified: usize,
+    pub num_conflict: usize,
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        let mut solver = Self {
+            clauses: Vec::new(),
+            watches: Vec::new(),
+            value: Vec::new(),
+            level: Vec::new(),
+            reason: Vec::new(),
+            phase: Vec::new(),
+            var_inc: 1.0,
+            order: VarOrder::default(),
+            trail: Vec::new(),
+            trail_lim: Vec::new(),
+            qhead: 0,
+            unsat: false,
+            model: Vec::new(),
+            failed: Vec::new(),
+            simplified: 0,
+            num_conflict: 0,
+        };
+        let constant = solver.new_var();
+        solver.add_clause(&[Lit::new(constant, true)]);
+        solver
+    }
+
+    pub fn num_var(&self) -> usize {
+        self.value.len()
+    }
+
+    pub fn new_var(&mut self) -> Var {
+        let var = self.value.len();
+        self.watches.push(Vec::new());
+        self.watches.push(Vec::new());
+        self.value.push(None);
+        self.level.push(0);
+        self.reason.push(None);
+        self.phase.push(false);
+        self.order.new_var();
+        var.into()
+    }
+
+    fn reserve_var(&mut self, var: Var) {
+        while self.num_var() <= usize::from(var) {
+            self.new_var();
+        }
+    }
+
+    fn decision_level(&self) -> usize {
+        self.trail_lim.len()
+    }
+
+    fn assign(&mut self, lit: Lit, reason: Option<usize>) {
+        let var = usize::from(lit.var());
+        debug_assert!(self.value[var].is_none());
+        self.value[var] = Some(!lit.compl());
+        self.level[var] = self.decision_level();
+        self.reason[var] = reason;
+        self.trail.push(lit);
+    }
+
+    fn attach(&mut self, clause: Vec<Lit>) -> usize {
+        let id = self.clauses.len();
+        self.watches[index(clause[0])].push(id);
+        self.watches[index(clause[1])].push(id);
+        self.clauses.push(clause);
+        id
+    }
+
+    /// Adds a clause, variables are created on demand.
+    pub fn add_clause(&mut self, clause: &[Lit]) {
+        assert!(self.decision_level() == 0);
+        if self.unsat {
+            return;
+        }
+        let mut lits = Vec::new();
+        for lit in clause.iter() {
+            self.reserve_var(lit.var());
+            match lit_value(&self.value, *lit) {
+                Some(true) => return,
+                Some(false) => continue,
+                None => {}
+            }
+            if lits.contains(&!*lit) {
+                return;
+            }
+            if !lits.contains(lit) {
+                lits.push(*lit);
+            }
+        }
+        match lits.len() {
+            0 => self.unsat = true,
+            1 => {
+                self.assign(lits[0], None);
+                self.unsat = self.propagate().is_some();
+            }
+            _ => {
+                self.attach(lits);
+            }
+        }
+    }
+
+    pub fn add_cnf(&mut self, cnf: &Cnf) {
+        for clause in cnf.iter() {
+            self.add_clause(clause);
+        }
+    }
+
+    fn propagate(&mut self) -> Option<usize> {
+        while self.qhead < self.trail.len() {
+            let false_lit = !self.trail[self.qhead];
+            self.qhead += 1;
+            let mut watches = take(&mut self.watches[index(false_lit)]);
+            let mut conflict = None;
+            let mut i = 0;
+            while i < watches.len() {
+                let id = watches[i];
+                let clause = &mut self.clauses[id];
+                if clause[0] == false_lit {
+                    clause.swap(0, 1);
+                }
+                if lit_value(&self.value, clause[0]) == Some(true) {
+                    i += 1;
+                    continue;
+                }
+                if let Some(k) =
+                    (2..clause.len()).find(|k| lit_value(&self.value, clause[*k]) != Some(false))
+                {
+                    clause.swap(1, k);
+                    self.watches[index(clause[1])].push(id);
+                    watches.swap_remove(i);
+                    continue;
+                }
+                let first = clause[0];
+                if lit_value(&self.value, first) == Some(false) {
+                    conflict = Some(id);
+                    break;
+                }
+                self.assign(first, Some(id));
+                i += 1;
+            }
+            self.watches[index(false_lit)] = watches;
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+        None
+    }
+
+    fn bump(&mut self, var: usize) {
+        if self.order.bump(var, self.var_inc) {
+            self.var_inc *= 1e-100;
+        }
+    }
+
+    /// First-UIP conflict analysis, returns the learnt clause with the asserting literal first
+    /// and a literal of the backjump level second.
+    fn analyze(&mut self, conflict: usize) -> (Vec<Lit>, usize) {
+        let mut seen = vec![false; self.num_var()];
+        let mut learnt = vec![Lit::constant_lit(false)];
+        let mut counter = 0;
+        let mut clause = conflict;
+        let mut start = 0;
+        let mut index = self.trail.len();
+        let uip = loop {
+            for k in start..self.clauses[clause].len() {
+                let lit = self.clauses[clause][k];
+                let var = usize::from(lit.var());
+                if seen[var] || self.level[var] == 0 {
+                    continue;
+                }
+                seen[var] = true;
+                self.bump(var);
+                if self.level[var] == self.decision_level() {
+                    counter += 1;
+                } else {
+                    learnt.push(lit);
+                }
+            }
+            loop {
+                index -= 1;
+                if seen[usize::from(self.trail[index].var())] {
+                    break;
+                }
+            }
+            let lit = self.trail[index];
+            counter -= 1;
+            if counter == 0 {
+                break lit;
+            }
+            clause = self.reason[usize::from(lit.var())].unwrap();
+            start = 1;
+        };
+        learnt[0] = !uip;
+        let mut backjump = 0;
+        for k in 1..learnt.len() {
+            let level = self.level[usize::from(learnt[k].var())];
+            if level > backjump {
+                backjump = level;
+                learnt.swap(1, k);
+            }
+        }
+        self.var_inc /= VAR_DECAY;
+        (learnt, backjump)
+    }
+
+    fn cancel_until(&mut self, level: usize) {
+        if self.decision_level() <= level {
+            return;
+        }
+        let lim = self.trail_lim[level];
+        for lit in self.trail.drain(lim..) {
+            let var = usize::from(lit.var());
+            self.phase[var] = !lit.compl();
+            self.value[var] = None;
+            self.reason[var] = None;
+            self.order.insert(var);
+        }
+        self.trail_lim.truncate(level);
+        self.qhead = self.trail.len();
+    }
+
+    fn pick_branch(&mut self) -> Option<Lit> {
+        while let Some(var) = self.order.pop() {
+            if self.value[var].is_none() {
+                return Some(Lit::new(var.into(), !self.phase[var]));
+            }
+        }
+        None
+    }
+
+    /// Removes the clauses satisfied at the root level, e.g. those of retired activation
+    /// literals, once enough of them have piled up.
+    fn simplify(&mut self) {
+        if self.trail.len() < self.simplified + SIMPLIFY_INTERVAL {
+            return;
+        }
+        self.simplified = self.trail.len();
+        let value = &self.value;
+        self.clauses.retain(|clause| {
+            !clause
+                .iter()
+                .any(|lit| lit_value(value, *lit) == Some(true))
+        });
+        for watches in self.watches.iter_mut() {
+            watches.clear();
+        }
+        for (id, clause) in self.clauses.iter().enumerate() {
+            self.watches[index(clause[0])].push(id);
+            self.watches[index(clause[1])].push(id);
+        }
+        for lit in self.trail.iter() {
+            self.reason[usize::from(lit.var())] = None;
+        }
+    }
+
+    /// Assumptions that imply the falsified assumption `lit`, `lit` included.
+    fn analyze_final(&mut self, lit: Lit) {
+        self.failed = vec![lit];
+        if self.decision_level() == 0 {
+            return;
+        }
+        let mut seen = vec![false; self.num_var()];
+        seen[usize::from(lit.var())] = true;
+        for i in (self.trail_lim[0]..self.trail.len()).rev() {
+            let var = usize::from(self.trail[i].var());
+            if !seen[var] {
+                continue;
+            }
+            match self.reason[var] {
+                None => self.failed.push(self.trail[i]),
+                Some(reason) => {
+                    for lit in self.clauses[reason][1..].iter() {
+                        if self.level[usize::from(lit.var())] > 0 {
+                            seen[usize::from(lit.var())] = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Solves under `assumptions`, the model is kept for `lit_value` when satisfiable and the
+    /// failed assumptions for `failed` otherwise.
+    pub fn solve(&mut self, assumptions: &[Lit]) -> bool {
+        self.failed.clear();
+        if self.unsat {
+            return false;
+        }
+        for lit in assumptions.iter() {
+            self.reserve_var(lit.var());
+        }
+        self.simplify();
+        let mut restart_limit = RESTART_FIRST as f64;
+        let mut conflicts = 0;
+        let res = loop {
+            if let Some(conflict) = self.propagate() {
+                self.num_conflict += 1;
+                conflicts += 1;
+                if self.decision_level() == 0 {
+                    self.unsat = true;
+                    break false;
+                }
+                let (learnt, backjump) = self.analyze(conflict);
+                self.cancel_until(backjump);
+                if learnt.len() == 1 {
+                    self.assign(learnt[0], None);
+                } else {
+                    let asserting = learnt[0];
+                    let id = self.attach(learnt);
+                    self.assign(asserting, Some(id));
+                }
+                continue;
+            }
+            if conflicts as f64 >= restart_limit {
+                conflicts = 0;
+                restart_limit *= RESTART_INC;
+                self.cancel_until(0);
+                continue;
+            }
+            let decision = if self.decision_level() < assumptions.len() {
+                let lit = assumptions[self.decision_level()];
+                match lit_value(&self.value, lit) {
+                    Some(true) => {
+                        self.trail_lim.push(self.trail.len());
+                        continue;
+                    }
+                    Some(false) => {
+                        self.analyze_final(lit);
+                        break false;
+                    }
+                    None => lit,
+                }
+            } else {
+                match self.pick_branch() {
+                    Some(lit) => lit,
+                    None => {
+                        self.model = self.value.clone();
+                        break true;
+                    }
+                }
+            };
+            self.trail_lim.push(self.trail.len());
+            self.assign(decision, None);
+        };
+        self.cancel_until(0);
+        res
+    }
+
+    /// Whether the assumption `lit` is part of the unsatisfiable core of the last call.
+    pub fn failed(&self, lit: Lit) -> bool {
+        self.failed.contains(&lit)
+    }
+
+    /// Value of `lit` in the last model.
+    pub fn lit_value(&self, lit: Lit) -> Option<bool> {
+        self.model
+            .get(usize::from(lit.var()))
+            .and_then(|v| v.map(|v| v != lit.compl()))
+    }
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Solver;
+    use logic_form::{Lit, Var};
+
+    fn pigeonhole(solver: &mut Solver, pigeons: usize, holes: usize) {
+        let var = |p: usize, h: usize| Var::from(1 + p * holes + h);
+        for p in 0..pigeons {
+            let clause: Vec<Lit> = (0..holes).map(|h| Lit::new(var(p, h), false)).collect();
+            solver.add_clause(&clause);
+        }
+        for h in 0..holes {
+            for p in 0..pigeons {
+                for q in p + 1..pigeons {
+                    solver.add_clause(&[Lit::new(var(p, h), true), Lit::new(var(q, h), true)]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_pigeonhole() {
+        let mut solver = Solver::new();
+        pigeonhole(&mut solver, 5, 5);
+        assert!(solver.solve(&[]));
+        let mut solver = Solver::new();
+        pigeonhole(&mut solver, 6, 5);
+        assert!(!solver.solve(&[]));
+    }
+
+    #[test]
+    fn test_assumptions() {
+        let mut solver = Solver::new();
+        let a = Lit::new(solver.new_var(), false);
+        let b = Lit::new(solver.new_var(), false);
+        let c = Lit::new(solver.new_var(), false);
+        solver.add_clause(&[!a, b]);
+        solver.add_clause(&[!b, c]);
+        assert!(solver.solve(&[a]));
+        assert_eq!(solver.lit_value(c), Some(true));
+        assert!(!solver.solve(&[a, !c]));
+        assert!(solver.solve(&[!c]));
+        assert_eq!(solver.lit_value(a), Some(false));
+        assert_eq!(solver.lit_value(Lit::constant_lit(true)), Some(true));
+        let d = Lit::new(solver.new_var(), false);
+        assert!(!solver.solve(&[d, a, !c]));
+        assert!(solver.failed(a) && solver.failed(!c) && !solver.failed(d));
+    }
+}
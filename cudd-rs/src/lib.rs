@@ -0,0 +1,225 @@
+mod node;
+pub use node::*;
+
+use cudd_sys::*;
+use std::{
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+pub(crate) struct Manager {
+    pub(crate) dd: *mut DdManager,
+    reorder: AtomicBool,
+    grouped: AtomicUsize,
+}
+
+unsafe impl Sync for Manager {}
+
+unsafe impl Send for Manager {}
+
+impl Drop for Manager {
+    fn drop(&mut self) {
+        unsafe { Cudd_Quit(self.dd) }
+    }
+}
+
+/// A CUDD manager. Unlike Sylvan, every `Cudd::new()` creates an independent node table.
+///
+/// CUDD itself is not thread-safe, a manager and its BDDs must only be used by one thread at
+/// a time. They are marked `Send` and `Sync` so that independent managers can be moved to
+/// separate workers.
+#[derive(Clone)]
+pub struct Cudd {
+    pub(crate) manager: Arc<Manager>,
+}
+
+impl Cudd {
+    pub fn new() -> Self {
+        let dd = unsafe { Cudd_Init(0, 0, CUDD_UNIQUE_SLOTS, CUDD_CACHE_SLOTS, 0) };
+        assert!(!dd.is_null());
+        Self {
+            manager: Arc::new(Manager {
+                dd,
+                reorder: AtomicBool::new(false),
+                grouped: AtomicUsize::new(0),
+            }),
+        }
+    }
+
+    pub(crate) fn dd(&self) -> *mut DdManager {
+        self.manager.dd
+    }
+
+    pub fn constant(&self, val: bool) -> Bdd {
+        let node = if val {
+            unsafe { Cudd_ReadOne(self.dd()) }
+        } else {
+            unsafe { Cudd_ReadLogicZero(self.dd()) }
+        };
+        Bdd::new(self, node)
+    }
+
+    pub fn ith_var(&self, i: usize) -> Bdd {
+        let num_var = self.num_var();
+        let node = unsafe { Cudd_bddIthVar(self.dd(), i as _) };
+        if i >= num_var && self.manager.reorder.load(Ordering::Relaxed) {
+            self.group_state_vars();
+        }
+        Bdd::new(self, node)
+    }
+
+    pub fn num_var(&self) -> usize {
+        unsafe { Cudd_ReadSize(self.dd()) as usize }
+    }
+
+    pub fn cube<I: IntoIterator<Item = (usize, bool)>>(&self, vars: I) -> Bdd {
+        let (mut vars, mut phase): (Vec<_>, Vec<_>) = vars
+            .into_iter()
+            .map(|(var, pol)| (self.ith_var(var), pol as i32))
+            .unzip();
+        let mut nodes: Vec<*mut DdNode> = vars.iter_mut().map(|var| var.node).collect();
+        let node = unsafe {
+            Cudd_bddComputeCube(
+                self.dd(),
+                nodes.as_mut_ptr(),
+                phase.as_mut_ptr(),
+                nodes.len() as _,
+            )
+        };
+        Bdd::new(self, node)
+    }
+
+    /// Moves `bdd` (possibly owned by another manager) into this manager.
+    pub fn translocate(&self, bdd: &Bdd) -> Bdd {
+        if *self == bdd.manager {
+            return bdd.clone();
+        }
+        let node = unsafe { Cudd_bddTransfer(bdd.manager.dd(), self.dd(), bdd.node) };
+        Bdd::new(self, node)
+    }
+
+    fn group_state_vars(&self) {
+        let grouped = self.manager.grouped.load(Ordering::Relaxed);
+        let num_var = self.num_var();
+        for i in (grouped..num_var).step_by(2) {
+            unsafe { Cudd_MakeTreeNode(self.dd(), i as _, 2, MTR_FIXED) };
+        }
+        self.manager
+            .grouped
+            .store(grouped.max(num_var + num_var % 2), Ordering::Relaxed);
+    }
+
+    /// Enables dynamic variable reordering. Each current/next state variable pair is grouped
+    /// (also for variables created later) so that the interleaved encoding is kept together
+    /// while sifting.
+    pub fn enable_reordering(&self) {
+        self.manager.reorder.store(true, Ordering::Relaxed);
+        self.group_state_vars();
+        unsafe { Cudd_AutodynEnable(self.dd(), CUDD_REORDER_GROUP_SIFT) }
+    }
+
+    pub fn disable_reordering(&self) {
+        self.manager.reorder.store(false, Ordering::Relaxed);
+        unsafe { Cudd_AutodynDisable(self.dd()) }
+    }
+
+    pub(crate) fn compose_map(&self, map: impl Fn(usize) -> usize) -> Vec<Bdd> {
+        (0..self.num_var()).map(|i| self.ith_var(map(i))).collect()
+    }
+}
+
+impl Default for Cudd {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for Cudd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Cudd({:?})", self.dd())
+    }
+}
+
+impl PartialEq for Cudd {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.manager, &other.manager)
+    }
+}
+
+impl Eq for Cudd {}
+
+#[cfg(test)]
+mod tests {
+    use crate::Cudd;
+
+    #[test]
+    fn test_basic() {
+        let cudd = Cudd::new();
+        let a = cudd.ith_var(0);
+        let b = cudd.ith_var(1);
+        let c = &a & &b;
+        let d = !(!a | !b);
+        assert_eq!(c, d);
+        let a = cudd.constant(true);
+        let b = cudd.constant(false);
+        assert_eq!(&a & &c, c);
+        assert_eq!(a, !b);
+    }
+
+    #[test]
+    fn test_image() {
+        let cudd = Cudd::new();
+        let a = cudd.ith_var(0);
+        let ap = cudd.ith_var(1);
+        let b = cudd.ith_var(2);
+        let bp = cudd.ith_var(3);
+        let s = &a & &b;
+        let t = &a & &b & !ap & !bp;
+        let next = s.post_image(&t);
+        assert_eq!(next, !&a & !&b);
+        let s = !&a & !&b;
+        let pre = s.pre_image(&t);
+        assert_eq!(pre, &a & &b);
+        let s = !&a & &b;
+        let pre = s.pre_image(&t);
+        assert_eq!(pre, cudd.constant(false));
+    }
+
+    #[test]
+    fn test_next_state() {
+        let cudd = Cudd::new();
+        let a = cudd.ith_var(0);
+        let ap = cudd.ith_var(1);
+        let b = cudd.ith_var(2);
+        let bp = cudd.ith_var(3);
+        let s = &a & &b;
+        assert_eq!(s.next_state(), &ap & &bp);
+        let sp = !&ap & !&bp;
+        assert_eq!(sp.previous_state(), !&a & !&b);
+    }
+
+    #[test]
+    fn test_support() {
+        let cudd = Cudd::new();
+        let a = cudd.ith_var(0);
+        let _ = cudd.ith_var(1);
+        let b = cudd.ith_var(2);
+        let s = &a & !&b;
+        assert_eq!(s.support(), &a & &b);
+        assert_eq!(s.support_index(), vec![0, 2]);
+        assert_eq!(cudd.cube([(0, true), (2, true)]), &a & &b);
+    }
+
+    #[test]
+    fn test_translocate() {
+        let x = Cudd::new();
+        let y = Cudd::new();
+        let f = x.ith_var(0) & !x.ith_var(3);
+        let g = y.translocate(&f);
+        assert_eq!(g, y.ith_var(0) & !y.ith_var(3));
+        assert_eq!(x.translocate(&g), f);
+    }
+}
@@ -0,0 +1,264 @@
+use crate::Cudd;
+use cudd_sys::*;
+use libc::free;
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not},
+    slice::from_raw_parts,
+};
+
+pub struct Bdd {
+    pub(crate) manager: Cudd,
+    pub(crate) node: *mut DdNode,
+}
+
+unsafe impl Sync for Bdd {}
+
+unsafe impl Send for Bdd {}
+
+impl Bdd {
+    pub(crate) fn new(manager: &Cudd, node: *mut DdNode) -> Self {
+        assert!(!node.is_null());
+        unsafe { Cudd_Ref(node) };
+        Self {
+            manager: manager.clone(),
+            node,
+        }
+    }
+
+    fn apply(
+        &self,
+        rhs: &Bdd,
+        f: unsafe extern "C" fn(*mut DdManager, *mut DdNode, *mut DdNode) -> *mut DdNode,
+    ) -> Self {
+        assert!(self.manager == rhs.manager);
+        let node = unsafe { f(self.manager.dd(), self.node, rhs.node) };
+        Bdd::new(&self.manager, node)
+    }
+}
+
+impl Drop for Bdd {
+    fn drop(&mut self) {
+        unsafe { Cudd_RecursiveDeref(self.manager.dd(), self.node) }
+    }
+}
+
+impl AsRef<Bdd> for Bdd {
+    fn as_ref(&self) -> &Bdd {
+        self
+    }
+}
+
+impl AsMut<Bdd> for Bdd {
+    fn as_mut(&mut self) -> &mut Bdd {
+        self
+    }
+}
+
+impl Clone for Bdd {
+    fn clone(&self) -> Self {
+        Self::new(&self.manager, self.node)
+    }
+}
+
+impl Debug for Bdd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Bdd({:?})", self.node)
+    }
+}
+
+impl PartialEq for Bdd {
+    fn eq(&self, other: &Self) -> bool {
+        self.manager == other.manager && self.node == other.node
+    }
+}
+
+impl Eq for Bdd {}
+
+impl Not for Bdd {
+    type Output = Bdd;
+
+    fn not(self) -> Self::Output {
+        !&self
+    }
+}
+
+impl Not for &Bdd {
+    type Output = Bdd;
+
+    fn not(self) -> Self::Output {
+        Bdd::new(&self.manager, unsafe { Cudd_Not(self.node) })
+    }
+}
+
+impl<T: AsRef<Bdd>> BitAnd<T> for Bdd {
+    type Output = Bdd;
+
+    fn bitand(self, rhs: T) -> Self::Output {
+        self.apply(rhs.as_ref(), Cudd_bddAnd)
+    }
+}
+
+impl<T: AsRef<Bdd>> BitAnd<T> for &Bdd {
+    type Output = Bdd;
+
+    fn bitand(self, rhs: T) -> Self::Output {
+        self.apply(rhs.as_ref(), Cudd_bddAnd)
+    }
+}
+
+impl<T: AsRef<Bdd>> BitAndAssign<T> for Bdd {
+    fn bitand_assign(&mut self, rhs: T) {
+        *self = &*self & rhs.as_ref();
+    }
+}
+
+impl<T: AsRef<Bdd>> BitOr<T> for Bdd {
+    type Output = Bdd;
+
+    fn bitor(self, rhs: T) -> Self::Output {
+        self.apply(rhs.as_ref(), Cudd_bddOr)
+    }
+}
+
+impl<T: AsRef<Bdd>> BitOr<T> for &Bdd {
+    type Output = Bdd;
+
+    fn bitor(self, rhs: T) -> Self::Output {
+        self.apply(rhs.as_ref(), Cudd_bddOr)
+    }
+}
+
+impl<T: AsRef<Bdd>> BitOrAssign<T> for Bdd {
+    fn bitor_assign(&mut self, rhs: T) {
+        *self = &*self | rhs.as_ref();
+    }
+}
+
+impl<T: AsRef<Bdd>> BitXor<T> for Bdd {
+    type Output = Bdd;
+
+    fn bitxor(self, rhs: T) -> Self::Output {
+        self.apply(rhs.as_ref(), Cudd_bddXor)
+    }
+}
+
+impl<T: AsRef<Bdd>> BitXor<T> for &Bdd {
+    type Output = Bdd;
+
+    fn bitxor(self, rhs: T) -> Self::Output {
+        self.apply(rhs.as_ref(), Cudd_bddXor)
+    }
+}
+
+impl<T: AsRef<Bdd>> BitXorAssign<T> for Bdd {
+    fn bitxor_assign(&mut self, rhs: T) {
+        *self = &*self ^ rhs.as_ref();
+    }
+}
+
+impl Bdd {
+    pub fn is_constant(&self, value: bool) -> bool {
+        *self == self.manager.constant(value)
+    }
+
+    pub fn size(&self) -> usize {
+        unsafe { Cudd_DagSize(self.node) as usize }
+    }
+
+    pub fn if_then_else(&self, _then: &Bdd, _else: &Bdd) -> Self {
+        let node = unsafe { Cudd_bddIte(self.manager.dd(), self.node, _then.node, _else.node) };
+        Bdd::new(&self.manager, node)
+    }
+
+    pub fn and_abstract<I: IntoIterator<Item = usize>>(&self, x: &Bdd, cube: I) -> Self {
+        let cube = self.manager.cube(cube.into_iter().map(|x| (x, true)));
+        let node = unsafe { Cudd_bddAndAbstract(self.manager.dd(), self.node, x.node, cube.node) };
+        Bdd::new(&self.manager, node)
+    }
+
+    pub fn support(&self) -> Self {
+        let node = unsafe { Cudd_Support(self.manager.dd(), self.node) };
+        Bdd::new(&self.manager, node)
+    }
+
+    pub fn support_index(&self) -> Vec<usize> {
+        let mut indices = std::ptr::null_mut();
+        let len = unsafe { Cudd_SupportIndices(self.manager.dd(), self.node, &mut indices) };
+        assert!(len >= 0);
+        if len == 0 {
+            return Vec::new();
+        }
+        let mut res: Vec<usize> = unsafe { from_raw_parts(indices, len as usize) }
+            .iter()
+            .map(|i| *i as usize)
+            .collect();
+        unsafe { free(indices as _) };
+        res.sort();
+        res
+    }
+
+    fn compose(&self, map: impl Fn(usize) -> usize) -> Self {
+        let vector = self.manager.compose_map(map);
+        let mut nodes: Vec<*mut DdNode> = vector.iter().map(|var| var.node).collect();
+        let node =
+            unsafe { Cudd_bddVectorCompose(self.manager.dd(), self.node, nodes.as_mut_ptr()) };
+        Bdd::new(&self.manager, node)
+    }
+
+    pub fn next_state(&self) -> Self {
+        self.compose(|i| if i % 2 == 0 { i + 1 } else { i })
+    }
+
+    pub fn previous_state(&self) -> Self {
+        self.compose(|i| if i % 2 == 1 { i - 1 } else { i })
+    }
+
+    pub fn post_image(&self, tran: &Bdd) -> Self {
+        let vars = (0..self.manager.num_var()).step_by(2);
+        self.and_abstract(tran, vars).previous_state()
+    }
+
+    pub fn pre_image(&self, tran: &Bdd) -> Self {
+        let vars = (1..self.manager.num_var()).step_by(2);
+        self.next_state().and_abstract(tran, vars)
+    }
+
+    /// Lists the nodes as `(var, low, high)`, children before parents. References `0` and `1`
+    /// are the constants, reference `i + 2` is the i-th node. Returns the nodes and the root.
+    pub fn export(&self) -> (Vec<(usize, usize, usize)>, usize) {
+        let one = unsafe { Cudd_ReadOne(self.manager.dd()) };
+        let mut nodes = Vec::new();
+        let mut map = HashMap::from([(unsafe { Cudd_Not(one) }, 0), (one, 1)]);
+        let mut stack = vec![self.node];
+        while let Some(node) = stack.last().copied() {
+            if map.contains_key(&node) {
+                stack.pop();
+                continue;
+            }
+            let regular = unsafe { Cudd_Regular(node) };
+            let (mut low, mut high) = unsafe { (Cudd_E(regular), Cudd_T(regular)) };
+            if Cudd_IsComplement(node) {
+                (low, high) = unsafe { (Cudd_Not(low), Cudd_Not(high)) };
+            }
+            match (map.get(&low), map.get(&high)) {
+                (Some(l), Some(h)) => {
+                    let var = unsafe { Cudd_NodeReadIndex(regular) } as usize;
+                    nodes.push((var, *l, *h));
+                    map.insert(node, nodes.len() + 1);
+                    stack.pop();
+                }
+                (l, h) => {
+                    if l.is_none() {
+                        stack.push(low);
+                    }
+                    if h.is_none() {
+                        stack.push(high);
+                    }
+                }
+            }
+        }
+        (nodes, map[&self.node])
+    }
+}
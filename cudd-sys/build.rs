@@ -0,0 +1,36 @@
+extern crate cmake;
+
+use cmake::Config;
+use std::env;
+use std::path::PathBuf;
+
+fn main() -> Result<(), String> {
+    let build_cudd = env::var_os("CARGO_FEATURE_BUILD_CUDD").is_some();
+    if !build_cudd {
+        // If silent build is active, don't do anything.
+        return Ok(());
+    }
+
+    let out_dir = env::var("OUT_DIR")
+        .map_err(|_| "Environmental variable `OUT_DIR` not defined.".to_string())?;
+
+    if !PathBuf::from("../cudd/cudd/cudd.h").exists() {
+        return Err(
+            "CUDD sources not vendored in ../cudd, import them with cudd-sys/fetch-cudd.sh"
+                .to_string(),
+        );
+    }
+
+    let wrapper_path = PathBuf::from("wrapper");
+    println!("cargo:rerun-if-changed=../cudd");
+    println!("cargo:rerun-if-changed=./wrapper");
+    Config::new(wrapper_path).build();
+
+    println!(
+        "cargo:rustc-link-search=native={}",
+        PathBuf::from(out_dir).join("lib").display()
+    );
+    println!("cargo:rustc-link-lib=static=cudd");
+
+    Ok(())
+}
@@ -0,0 +1,15 @@
+#!/bin/sh
+# Imports the CUDD 3.0.0 sources into ../cudd, where ./wrapper/CMakeLists.txt expects them.
+# Commit them there to vendor them, the build itself never downloads.
+# Set CUDD_URL to use another copy of the release archive.
+set -eu
+cd "$(dirname "$0")/.."
+if [ -f cudd/cudd/cudd.h ]; then
+    echo "cudd/ already holds the CUDD sources"
+    exit 0
+fi
+url=${CUDD_URL:-https://github.com/ivmai/cudd/archive/refs/tags/cudd-3.0.0.tar.gz}
+mkdir -p cudd
+curl -fsSL "$url" | tar -xz --strip-components=1 -C cudd
+test -f cudd/cudd/cudd.h
+echo "fetched the CUDD sources into cudd/"
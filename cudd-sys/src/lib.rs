@@ -0,0 +1,164 @@
+//! Unsafe Rust bindings for the CUDD decision diagram package (version `3.0.0`).
+//!
+//! Only the part of the `cudd.h` interface that is needed for BDD based model checking is
+//! reproduced here. The CUDD sources are expected to be vendored in `../cudd` and are built
+//! by `./wrapper/CMakeLists.txt`. The pointer tagging macros (`Cudd_Not`, `Cudd_Regular`, ...)
+//! are reimplemented in Rust since they are not exported as functions.
+
+// Allow non-idiomatic names in the whole crate.
+#![allow(non_camel_case_types)]
+#![allow(non_snake_case)]
+extern crate libc;
+
+use libc::{c_double, c_int, c_uint, c_void, size_t};
+
+#[repr(C)]
+pub struct DdManager {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+pub struct DdNode {
+    _private: [u8; 0],
+}
+
+pub const CUDD_UNIQUE_SLOTS: c_uint = 256;
+pub const CUDD_CACHE_SLOTS: c_uint = 262144;
+
+/// Variable reordering methods (`Cudd_ReorderingType`).
+pub type Cudd_ReorderingType = c_uint;
+pub const CUDD_REORDER_SAME: Cudd_ReorderingType = 0;
+pub const CUDD_REORDER_NONE: Cudd_ReorderingType = 1;
+pub const CUDD_REORDER_RANDOM: Cudd_ReorderingType = 2;
+pub const CUDD_REORDER_RANDOM_PIVOT: Cudd_ReorderingType = 3;
+pub const CUDD_REORDER_SIFT: Cudd_ReorderingType = 4;
+pub const CUDD_REORDER_SIFT_CONVERGE: Cudd_ReorderingType = 5;
+pub const CUDD_REORDER_SYMM_SIFT: Cudd_ReorderingType = 6;
+pub const CUDD_REORDER_SYMM_SIFT_CONV: Cudd_ReorderingType = 7;
+pub const CUDD_REORDER_WINDOW2: Cudd_ReorderingType = 8;
+pub const CUDD_REORDER_WINDOW3: Cudd_ReorderingType = 9;
+pub const CUDD_REORDER_WINDOW4: Cudd_ReorderingType = 10;
+pub const CUDD_REORDER_WINDOW2_CONV: Cudd_ReorderingType = 11;
+pub const CUDD_REORDER_WINDOW3_CONV: Cudd_ReorderingType = 12;
+pub const CUDD_REORDER_WINDOW4_CONV: Cudd_ReorderingType = 13;
+pub const CUDD_REORDER_GROUP_SIFT: Cudd_ReorderingType = 14;
+pub const CUDD_REORDER_GROUP_SIFT_CONV: Cudd_ReorderingType = 15;
+pub const CUDD_REORDER_ANNEALING: Cudd_ReorderingType = 16;
+pub const CUDD_REORDER_GENETIC: Cudd_ReorderingType = 17;
+pub const CUDD_REORDER_LINEAR: Cudd_ReorderingType = 18;
+pub const CUDD_REORDER_LINEAR_CONVERGE: Cudd_ReorderingType = 19;
+pub const CUDD_REORDER_LAZY_SIFT: Cudd_ReorderingType = 20;
+pub const CUDD_REORDER_EXACT: Cudd_ReorderingType = 21;
+
+/// Variable group flags for `Cudd_MakeTreeNode`.
+pub const MTR_DEFAULT: c_uint = 0;
+pub const MTR_FIXED: c_uint = 4;
+
+extern "C" {
+    pub fn Cudd_Init(
+        numVars: c_uint,
+        numVarsZ: c_uint,
+        numSlots: c_uint,
+        cacheSize: c_uint,
+        maxMemory: size_t,
+    ) -> *mut DdManager;
+    pub fn Cudd_Quit(unique: *mut DdManager);
+
+    pub fn Cudd_ReadOne(dd: *mut DdManager) -> *mut DdNode;
+    pub fn Cudd_ReadLogicZero(dd: *mut DdManager) -> *mut DdNode;
+    pub fn Cudd_ReadSize(dd: *mut DdManager) -> c_int;
+    pub fn Cudd_bddIthVar(dd: *mut DdManager, i: c_int) -> *mut DdNode;
+
+    pub fn Cudd_Ref(n: *mut DdNode);
+    pub fn Cudd_RecursiveDeref(table: *mut DdManager, n: *mut DdNode);
+
+    pub fn Cudd_IsConstant(node: *mut DdNode) -> c_int;
+    pub fn Cudd_NodeReadIndex(node: *mut DdNode) -> c_uint;
+    pub fn Cudd_T(node: *mut DdNode) -> *mut DdNode;
+    pub fn Cudd_E(node: *mut DdNode) -> *mut DdNode;
+
+    pub fn Cudd_bddAnd(dd: *mut DdManager, f: *mut DdNode, g: *mut DdNode) -> *mut DdNode;
+    pub fn Cudd_bddOr(dd: *mut DdManager, f: *mut DdNode, g: *mut DdNode) -> *mut DdNode;
+    pub fn Cudd_bddXor(dd: *mut DdManager, f: *mut DdNode, g: *mut DdNode) -> *mut DdNode;
+    pub fn Cudd_bddIte(
+        dd: *mut DdManager,
+        f: *mut DdNode,
+        g: *mut DdNode,
+        h: *mut DdNode,
+    ) -> *mut DdNode;
+    pub fn Cudd_bddExistAbstract(
+        manager: *mut DdManager,
+        f: *mut DdNode,
+        cube: *mut DdNode,
+    ) -> *mut DdNode;
+    pub fn Cudd_bddAndAbstract(
+        manager: *mut DdManager,
+        f: *mut DdNode,
+        g: *mut DdNode,
+        cube: *mut DdNode,
+    ) -> *mut DdNode;
+    pub fn Cudd_bddVectorCompose(
+        dd: *mut DdManager,
+        f: *mut DdNode,
+        vector: *mut *mut DdNode,
+    ) -> *mut DdNode;
+    pub fn Cudd_bddComputeCube(
+        dd: *mut DdManager,
+        vars: *mut *mut DdNode,
+        phase: *mut c_int,
+        n: c_int,
+    ) -> *mut DdNode;
+    pub fn Cudd_bddTransfer(
+        ddSource: *mut DdManager,
+        ddDestination: *mut DdManager,
+        f: *mut DdNode,
+    ) -> *mut DdNode;
+
+    pub fn Cudd_Support(dd: *mut DdManager, f: *mut DdNode) -> *mut DdNode;
+    pub fn Cudd_SupportIndices(
+        dd: *mut DdManager,
+        f: *mut DdNode,
+        indices: *mut *mut c_int,
+    ) -> c_int;
+    pub fn Cudd_DagSize(node: *mut DdNode) -> c_int;
+    pub fn Cudd_CountMinterm(manager: *mut DdManager, node: *mut DdNode, nvars: c_int) -> c_double;
+
+    pub fn Cudd_AutodynEnable(unique: *mut DdManager, method: Cudd_ReorderingType);
+    pub fn Cudd_AutodynDisable(unique: *mut DdManager);
+    pub fn Cudd_ReduceHeap(
+        table: *mut DdManager,
+        heuristic: Cudd_ReorderingType,
+        minsize: c_int,
+    ) -> c_int;
+    pub fn Cudd_MakeTreeNode(
+        dd: *mut DdManager,
+        low: c_uint,
+        size: c_uint,
+        type_: c_uint,
+    ) -> *mut c_void;
+    pub fn Cudd_ReadPerm(dd: *mut DdManager, i: c_int) -> c_int;
+}
+
+/// Equivalent of the `Cudd_Not` macro.
+///
+/// # Safety
+/// The pointer is only retagged, it has to point to a node of a living manager to be useful.
+#[inline]
+pub unsafe fn Cudd_Not(node: *mut DdNode) -> *mut DdNode {
+    (node as usize ^ 1) as *mut DdNode
+}
+
+/// Equivalent of the `Cudd_Regular` macro.
+///
+/// # Safety
+/// The pointer is only retagged, it has to point to a node of a living manager to be useful.
+#[inline]
+pub unsafe fn Cudd_Regular(node: *mut DdNode) -> *mut DdNode {
+    (node as usize & !1) as *mut DdNode
+}
+
+/// Equivalent of the `Cudd_IsComplement` macro.
+#[inline]
+pub fn Cudd_IsComplement(node: *mut DdNode) -> bool {
+    node as usize & 1 == 1
+}
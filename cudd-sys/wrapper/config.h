@@ -0,0 +1,30 @@
+#ifndef CUDD_WRAPPER_CONFIG_H
+#define CUDD_WRAPPER_CONFIG_H
+
+/*
+    CUDD expects an autotools generated `config.h`. The build only targets
+    64-bit Linux and MacOS, so the relevant values are fixed here.
+*/
+
+#define HAVE_ASSERT_H 1
+#define HAVE_FLOAT_H 1
+#define HAVE_INTTYPES_H 1
+#define HAVE_LIMITS_H 1
+#define HAVE_MATH_H 1
+#define HAVE_STDDEF_H 1
+#define HAVE_STDINT_H 1
+#define HAVE_STDLIB_H 1
+#define HAVE_STRING_H 1
+#define HAVE_SYS_RESOURCE_H 1
+#define HAVE_SYS_TIME_H 1
+#define HAVE_SYS_TIMES_H 1
+#define HAVE_UNISTD_H 1
+#define HAVE_GETRUSAGE 1
+#define HAVE_GETRLIMIT 1
+#define HAVE_SYSCONF 1
+#define HAVE_POW 1
+#define HAVE_SQRT 1
+#define HAVE_STRCHR 1
+#define HAVE_STRSTR 1
+
+#endif
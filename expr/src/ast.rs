@@ -1,3 +1,4 @@
+use crate::Dialect;
 use std::{
     fmt::Display,
     ops::{BitAnd, BitOr, Not},
@@ -18,8 +19,8 @@ impl Display for Prefix {
         let display = match self {
             Prefix::Not => "!",
             Prefix::Next => "next",
-            Prefix::LtlGlobally => "[]",
-            Prefix::LtlFinally => "<>",
+            Prefix::LtlGlobally => "G",
+            Prefix::LtlFinally => "F",
             Prefix::LtlNext => "X",
             Prefix::LtlOnce => "O",
         };
@@ -42,8 +43,8 @@ pub enum Infix {
 impl Display for Infix {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let display = match self {
-            Infix::And => "&&",
-            Infix::Or => "||",
+            Infix::And => "&",
+            Infix::Or => "|",
             Infix::Xor => "xor",
             Infix::Imply => "->",
             Infix::Iff => "<->",
@@ -60,18 +61,6 @@ pub struct CaseExpr {
     pub branchs: Vec<(Expr, Expr)>,
 }
 
-impl Display for CaseExpr {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut previous_all_false = Expr::LitExpr(true);
-        let mut expr = Expr::LitExpr(false);
-        for (cond, res) in self.branchs.iter() {
-            expr = expr | (previous_all_false.clone() & cond.clone() & res.clone());
-            previous_all_false = previous_all_false & !cond.clone();
-        }
-        write!(f, "{}", expr)
-    }
-}
-
 #[derive(Debug, Clone, PartialEq, Hash, Eq)]
 pub enum Expr {
     Ident(String),
@@ -105,17 +94,10 @@ impl BitOr for Expr {
     }
 }
 
+/// Prints in the NuSMV dialect, see `Expr::display` for the others.
 impl Display for Expr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Expr::Ident(ident) => write!(f, "{}", ident),
-            Expr::LitExpr(lit) => {
-                write!(f, "{}", if *lit { "true" } else { "false" })
-            }
-            Expr::PrefixExpr(prefix, expr) => write!(f, "{}({})", prefix, expr),
-            Expr::InfixExpr(infix, left, right) => write!(f, "({}){}({})", left, infix, right),
-            Expr::CaseExpr(case_expr) => write!(f, "{}", case_expr),
-        }
+        write!(f, "{}", self.display(Dialect::NuSmv))
     }
 }
 
@@ -0,0 +1,61 @@
+use crate::{Expr, Infix, Prefix};
+use bdds::{Bdd, BddManager};
+use std::{
+    collections::HashMap,
+    ops::{BitAnd, BitOr, BitXor, Not},
+};
+
+impl Expr {
+    /// BDD of a propositional expression whose identifiers are all in `symbols`.
+    pub fn to_bdd<BM: BddManager>(
+        &self,
+        manager: &BM,
+        symbols: &HashMap<String, BM::Bdd>,
+    ) -> BM::Bdd
+    where
+        for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+            + BitAnd<BM::Bdd, Output = BM::Bdd>
+            + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+            + BitOr<BM::Bdd, Output = BM::Bdd>
+            + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+            + BitXor<BM::Bdd, Output = BM::Bdd>
+            + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+    {
+        match self {
+            Expr::Ident(ident) => match symbols.get(ident) {
+                Some(bdd) => bdd.clone(),
+                None => panic!("unknown symbol {}", ident),
+            },
+            Expr::LitExpr(lit) => manager.constant(*lit),
+            Expr::PrefixExpr(op, sub_expr) => {
+                let expr_bdd = sub_expr.to_bdd(manager, symbols);
+                match op {
+                    Prefix::Not => !expr_bdd,
+                    // `X` of a single step, as printed for `next` in the Spot and ltl2ba dialects
+                    Prefix::Next | Prefix::LtlNext => expr_bdd.next_state(),
+                    _ => panic!("temporal operator {} in a propositional expression", op),
+                }
+            }
+            Expr::InfixExpr(op, left, right) => {
+                let left_bdd = left.to_bdd(manager, symbols);
+                let right_bdd = right.to_bdd(manager, symbols);
+                match op {
+                    Infix::Or => left_bdd | right_bdd,
+                    Infix::And => left_bdd & right_bdd,
+                    Infix::Xor => left_bdd ^ right_bdd,
+                    Infix::Imply => !left_bdd | right_bdd,
+                    Infix::Iff => !(left_bdd ^ right_bdd),
+                    _ => panic!("temporal operator {} in a propositional expression", op),
+                }
+            }
+            Expr::CaseExpr(case_expr) => {
+                let mut ans = manager.constant(false);
+                for (cond, res) in case_expr.branchs.iter().rev() {
+                    let cond = cond.to_bdd(manager, symbols);
+                    ans = cond.if_then_else(&res.to_bdd(manager, symbols), &ans);
+                }
+                ans
+            }
+        }
+    }
+}
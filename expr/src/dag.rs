@@ -0,0 +1,393 @@
+use crate::{CaseExpr, Expr, Infix, Prefix};
+use bdds::{Bdd, BddManager};
+use std::{
+    collections::HashMap,
+    ops::{BitAnd, BitOr, BitXor, Not},
+};
+
+/// Index of a node in an `ExprDag`.
+pub type ExprId = usize;
+
+/// An expression whose subexpressions are nodes of the same `ExprDag`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ExprNode {
+    Ident(String),
+    LitExpr(bool),
+    PrefixExpr(Prefix, ExprId),
+    InfixExpr(Infix, ExprId, ExprId),
+    CaseExpr(Vec<(ExprId, ExprId)>),
+}
+
+/// Hash-consed expressions, structurally equal expressions are the same node. The rewrites
+/// take a cache from the nodes already rewritten to their result, so a node shared by many
+/// expressions is rewritten once.
+#[derive(Default, Debug, Clone)]
+pub struct ExprDag {
+    nodes: Vec<ExprNode>,
+    ids: HashMap<ExprNode, ExprId>,
+}
+
+impl ExprDag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn node(&self, id: ExprId) -> &ExprNode {
+        &self.nodes[id]
+    }
+
+    pub fn intern(&mut self, node: ExprNode) -> ExprId {
+        if let Some(id) = self.ids.get(&node) {
+            return *id;
+        }
+        let id = self.nodes.len();
+        self.nodes.push(node.clone());
+        self.ids.insert(node, id);
+        id
+    }
+
+    pub fn insert(&mut self, expr: &Expr) -> ExprId {
+        let node = match expr {
+            Expr::Ident(ident) => ExprNode::Ident(ident.clone()),
+            Expr::LitExpr(lit) => ExprNode::LitExpr(*lit),
+            Expr::PrefixExpr(op, sub_expr) => {
+                ExprNode::PrefixExpr(op.clone(), self.insert(sub_expr))
+            }
+            Expr::InfixExpr(op, left, right) => {
+                let left = self.insert(left);
+                let right = self.insert(right);
+                ExprNode::InfixExpr(op.clone(), left, right)
+            }
+            Expr::CaseExpr(case_expr) => ExprNode::CaseExpr(
+                case_expr
+                    .branchs
+                    .iter()
+                    .map(|(cond, res)| (self.insert(cond), self.insert(res)))
+                    .collect(),
+            ),
+        };
+        self.intern(node)
+    }
+
+    /// The tree of `id`, a shared node is copied at each of its occurrences.
+    pub fn to_expr(&self, id: ExprId) -> Expr {
+        match &self.nodes[id] {
+            ExprNode::Ident(ident) => Expr::Ident(ident.clone()),
+            ExprNode::LitExpr(lit) => Expr::LitExpr(*lit),
+            ExprNode::PrefixExpr(op, sub_expr) => {
+                Expr::PrefixExpr(op.clone(), Box::new(self.to_expr(*sub_expr)))
+            }
+            ExprNode::InfixExpr(op, left, right) => Expr::InfixExpr(
+                op.clone(),
+                Box::new(self.to_expr(*left)),
+                Box::new(self.to_expr(*right)),
+            ),
+            ExprNode::CaseExpr(branchs) => Expr::CaseExpr(CaseExpr {
+                branchs: branchs
+                    .iter()
+                    .map(|(cond, res)| (self.to_expr(*cond), self.to_expr(*res)))
+                    .collect(),
+            }),
+        }
+    }
+
+    /// The trees of `roots` without copying shared nodes: every node other than a leaf that is
+    /// free of LTL operators and occurs more than once below `roots` becomes an identifier
+    /// `name(i)`. The named nodes come second, each defined over the names before it.
+    pub fn to_exprs_with_defines<F>(
+        &self,
+        roots: &[ExprId],
+        mut name: F,
+    ) -> (Vec<Expr>, Vec<(String, Expr)>)
+    where
+        F: FnMut(usize) -> String,
+    {
+        // a node is interned after its children, so parents have the larger ids
+        let mut occurs = vec![0usize; self.nodes.len()];
+        for root in roots {
+            occurs[*root] += 1;
+        }
+        for id in (0..self.nodes.len()).rev() {
+            if occurs[id] > 0 {
+                for child in self.children(id) {
+                    occurs[child] += 1;
+                }
+            }
+        }
+        let mut temporal = vec![false; self.nodes.len()];
+        let mut names = HashMap::new();
+        let mut defines = Vec::new();
+        for id in 0..self.nodes.len() {
+            let leaf = matches!(self.nodes[id], ExprNode::Ident(_) | ExprNode::LitExpr(_));
+            temporal[id] = match &self.nodes[id] {
+                ExprNode::Ident(_) | ExprNode::LitExpr(_) => false,
+                ExprNode::PrefixExpr(Prefix::Not | Prefix::Next, _) => false,
+                ExprNode::PrefixExpr(..) => true,
+                ExprNode::InfixExpr(
+                    Infix::And | Infix::Or | Infix::Xor | Infix::Imply | Infix::Iff,
+                    ..,
+                ) => false,
+                ExprNode::InfixExpr(..) => true,
+                ExprNode::CaseExpr(_) => false,
+            } || self.children(id).any(|child| temporal[child]);
+            if occurs[id] > 1 && !leaf && !temporal[id] {
+                let define = self.to_named_expr(id, &names);
+                let ident = name(defines.len());
+                names.insert(id, ident.clone());
+                defines.push((ident, define));
+            }
+        }
+        let exprs = roots
+            .iter()
+            .map(|root| match names.get(root) {
+                Some(ident) => Expr::Ident(ident.clone()),
+                None => self.to_named_expr(*root, &names),
+            })
+            .collect();
+        (exprs, defines)
+    }
+
+    fn children(&self, id: ExprId) -> impl Iterator<Item = ExprId> + '_ {
+        let children: Vec<ExprId> = match &self.nodes[id] {
+            ExprNode::Ident(_) | ExprNode::LitExpr(_) => Vec::new(),
+            ExprNode::PrefixExpr(_, sub_expr) => vec![*sub_expr],
+            ExprNode::InfixExpr(_, left, right) => vec![*left, *right],
+            ExprNode::CaseExpr(branchs) => branchs
+                .iter()
+                .flat_map(|(cond, res)| [*cond, *res])
+                .collect(),
+        };
+        children.into_iter()
+    }
+
+    /// The tree of `id` whose children in `names` are their identifiers.
+    fn to_named_expr(&self, id: ExprId, names: &HashMap<ExprId, String>) -> Expr {
+        let child = |id: &ExprId| match names.get(id) {
+            Some(ident) => Expr::Ident(ident.clone()),
+            None => self.to_named_expr(*id, names),
+        };
+        match &self.nodes[id] {
+            ExprNode::Ident(ident) => Expr::Ident(ident.clone()),
+            ExprNode::LitExpr(lit) => Expr::LitExpr(*lit),
+            ExprNode::PrefixExpr(op, sub_expr) => {
+                Expr::PrefixExpr(op.clone(), Box::new(child(sub_expr)))
+            }
+            ExprNode::InfixExpr(op, left, right) => {
+                Expr::InfixExpr(op.clone(), Box::new(child(left)), Box::new(child(right)))
+            }
+            ExprNode::CaseExpr(branchs) => Expr::CaseExpr(CaseExpr {
+                branchs: branchs
+                    .iter()
+                    .map(|(cond, res)| (child(cond), child(res)))
+                    .collect(),
+            }),
+        }
+    }
+
+    /// `id` with its children replaced by `f` of them.
+    fn map_children<F>(&mut self, id: ExprId, mut f: F) -> ExprId
+    where
+        F: FnMut(&mut Self, ExprId) -> ExprId,
+    {
+        let node = match self.nodes[id].clone() {
+            ExprNode::Ident(_) | ExprNode::LitExpr(_) => return id,
+            ExprNode::PrefixExpr(op, sub_expr) => ExprNode::PrefixExpr(op, f(self, sub_expr)),
+            ExprNode::InfixExpr(op, left, right) => {
+                let left = f(self, left);
+                let right = f(self, right);
+                ExprNode::InfixExpr(op, left, right)
+            }
+            ExprNode::CaseExpr(branchs) => ExprNode::CaseExpr(
+                branchs
+                    .into_iter()
+                    .map(|(cond, res)| (f(self, cond), f(self, res)))
+                    .collect(),
+            ),
+        };
+        self.intern(node)
+    }
+
+    /// Replaces the identifiers of `defines` by their definitions, recursively.
+    pub fn expand_defines(
+        &mut self,
+        id: ExprId,
+        defines: &HashMap<String, ExprId>,
+        cache: &mut HashMap<ExprId, ExprId>,
+    ) -> ExprId {
+        if let Some(res) = cache.get(&id) {
+            return *res;
+        }
+        let res = match &self.nodes[id] {
+            ExprNode::Ident(ident) => match defines.get(ident) {
+                Some(define) => self.expand_defines(*define, defines, cache),
+                None => id,
+            },
+            _ => self.map_children(id, |dag, child| dag.expand_defines(child, defines, cache)),
+        };
+        cache.insert(id, res);
+        res
+    }
+
+    /// Rewrites the case expressions into `c0 & r0 | !c0 & (...)`, false if no branch applies
+    /// like [`Expr::to_bdd`]. A `TRUE` condition drops the branches after it.
+    pub fn lower_case(&mut self, id: ExprId, cache: &mut HashMap<ExprId, ExprId>) -> ExprId {
+        if let Some(res) = cache.get(&id) {
+            return *res;
+        }
+        let res = match self.nodes[id].clone() {
+            ExprNode::CaseExpr(branchs) => {
+                let mut ans = self.intern(ExprNode::LitExpr(false));
+                for (cond, res) in branchs.iter().rev() {
+                    let cond = self.lower_case(*cond, cache);
+                    let res = self.lower_case(*res, cache);
+                    if self.nodes[cond] == ExprNode::LitExpr(true) {
+                        ans = res;
+                        continue;
+                    }
+                    let then = self.intern(ExprNode::InfixExpr(Infix::And, cond, res));
+                    let not_cond = self.intern(ExprNode::PrefixExpr(Prefix::Not, cond));
+                    let other = self.intern(ExprNode::InfixExpr(Infix::And, not_cond, ans));
+                    ans = self.intern(ExprNode::InfixExpr(Infix::Or, then, other));
+                }
+                ans
+            }
+            _ => self.map_children(id, |dag, child| dag.lower_case(child, cache)),
+        };
+        cache.insert(id, res);
+        res
+    }
+
+    /// BDD of a propositional node whose identifiers are all in `symbols`.
+    pub fn to_bdd<BM: BddManager>(
+        &self,
+        id: ExprId,
+        manager: &BM,
+        symbols: &HashMap<String, BM::Bdd>,
+        cache: &mut HashMap<ExprId, BM::Bdd>,
+    ) -> BM::Bdd
+    where
+        for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+            + BitAnd<BM::Bdd, Output = BM::Bdd>
+            + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+            + BitOr<BM::Bdd, Output = BM::Bdd>
+            + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+            + BitXor<BM::Bdd, Output = BM::Bdd>
+            + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+    {
+        if let Some(bdd) = cache.get(&id) {
+            return bdd.clone();
+        }
+        let bdd = match &self.nodes[id] {
+            ExprNode::Ident(ident) => match symbols.get(ident) {
+                Some(bdd) => bdd.clone(),
+                None => panic!("unknown symbol {}", ident),
+            },
+            ExprNode::LitExpr(lit) => manager.constant(*lit),
+            ExprNode::PrefixExpr(op, sub_expr) => {
+                let expr_bdd = self.to_bdd(*sub_expr, manager, symbols, cache);
+                match op {
+                    Prefix::Not => !expr_bdd,
+                    Prefix::Next | Prefix::LtlNext => expr_bdd.next_state(),
+                    _ => panic!("temporal operator {} in a propositional expression", op),
+                }
+            }
+            ExprNode::InfixExpr(op, left, right) => {
+                let left_bdd = self.to_bdd(*left, manager, symbols, cache);
+                let right_bdd = self.to_bdd(*right, manager, symbols, cache);
+                match op {
+                    Infix::Or => left_bdd | right_bdd,
+                    Infix::And => left_bdd & right_bdd,
+                    Infix::Xor => left_bdd ^ right_bdd,
+                    Infix::Imply => !left_bdd | right_bdd,
+                    Infix::Iff => !(left_bdd ^ right_bdd),
+                    _ => panic!("temporal operator {} in a propositional expression", op),
+                }
+            }
+            ExprNode::CaseExpr(branchs) => {
+                let mut ans = manager.constant(false);
+                for (cond, res) in branchs.iter().rev() {
+                    let cond = self.to_bdd(*cond, manager, symbols, cache);
+                    let res = self.to_bdd(*res, manager, symbols, cache);
+                    ans = cond.if_then_else(&res, &ans);
+                }
+                ans
+            }
+        };
+        cache.insert(id, bdd.clone());
+        bdd
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExprDag, ExprNode};
+    use crate::{Dialect, Expr};
+    use peabody::Peabody;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_shared_defines() {
+        let mut dag = ExprDag::new();
+        let a = dag.insert(&Expr::parse("a & !b", Dialect::NuSmv));
+        assert_eq!(dag.insert(&Expr::parse("(a) & !(b)", Dialect::NuSmv)), a);
+        // every define refers to the previous one twice, its tree doubles at each step
+        let mut defines = HashMap::new();
+        defines.insert("d0".to_string(), a);
+        for i in 1..64 {
+            let define = format!("case d{0} : b; TRUE : !d{0}; esac", i - 1);
+            let define = dag.insert(&Expr::parse(&define, Dialect::NuSmv));
+            defines.insert(format!("d{}", i), define);
+        }
+        let root = dag.intern(ExprNode::Ident("d63".to_string()));
+        let root = dag.expand_defines(root, &defines, &mut HashMap::new());
+        let root = dag.lower_case(root, &mut HashMap::new());
+        assert!(dag.len() < 64 * 16);
+        let manager = Peabody::new();
+        let mut symbols = HashMap::new();
+        symbols.insert("a".to_string(), manager.ith_var(0));
+        symbols.insert("b".to_string(), manager.ith_var(2));
+        let bdd = dag.to_bdd(root, &manager, &symbols, &mut HashMap::new());
+        // d1 = !a | b, then the defines alternate between a | b and !a | b
+        assert_eq!(bdd, !&symbols["a"] | &symbols["b"]);
+        let small = dag.intern(ExprNode::Ident("d1".to_string()));
+        let small = dag.expand_defines(small, &defines, &mut HashMap::new());
+        assert_eq!(
+            dag.to_expr(small),
+            Expr::parse("case a & !b : b; TRUE : !(a & !b); esac", Dialect::NuSmv)
+        );
+    }
+
+    #[test]
+    fn test_case_without_default() {
+        // no branch applies if `!a & !b`, the case is false there and not `TRUE`
+        let expr = Expr::parse("case a : b; !a & b : TRUE; esac", Dialect::NuSmv);
+        let Expr::CaseExpr(case_expr) = &expr else {
+            panic!("not a case expression");
+        };
+        let manager = Peabody::new();
+        let mut symbols = HashMap::new();
+        symbols.insert("a".to_string(), manager.ith_var(0));
+        symbols.insert("b".to_string(), manager.ith_var(2));
+        let expected = symbols["b"].clone();
+        assert_eq!(expr.to_bdd(&manager, &symbols), expected);
+        assert_eq!(
+            case_expr.to_propositional().to_bdd(&manager, &symbols),
+            expected
+        );
+        let mut dag = ExprDag::new();
+        let root = dag.insert(&expr);
+        let bdd = dag.to_bdd(root, &manager, &symbols, &mut HashMap::new());
+        assert_eq!(bdd, expected);
+        let root = dag.lower_case(root, &mut HashMap::new());
+        let bdd = dag.to_bdd(root, &manager, &symbols, &mut HashMap::new());
+        assert_eq!(bdd, expected);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::{token::Token, Dialect};
+use nom::{
+    branch::alt,
+    bytes::complete::{is_not, tag},
+    character::complete::{alpha1, alphanumeric1, anychar, char, digit1, multispace0},
+    combinator::{map, map_res, recognize},
+    multi::{many0, many1},
+    sequence::{delimited, pair, tuple},
+    IResult,
+};
+use std::str::FromStr;
+
+macro_rules! syntax {
+    ($func_name: ident, $tag_string: literal, $output_token: expr) => {
+        fn $func_name(s: &str) -> IResult<&str, Token> {
+            map(tag($tag_string), |_| $output_token)(s)
+        }
+    };
+}
+
+syntax! {and_and_operator, "&&", Token::And}
+syntax! {and_operator, "&", Token::And}
+syntax! {or_or_operator, "||", Token::Or}
+syntax! {or_operator, "|", Token::Or}
+syntax! {question_operator, "?", Token::Conditional}
+syntax! {becomes_operator, ":=", Token::Becomes}
+syntax! {not_operator, "!", Token::Not}
+syntax! {iff_operator, "<->", Token::Iff}
+syntax! {imply_operator, "->", Token::Imply}
+syntax! {globally_operator, "[]", Token::LtlGlobally}
+syntax! {finally_operator, "<>", Token::LtlFinally}
+
+pub fn lex_operator(input: &str) -> IResult<&str, Token> {
+    alt((
+        and_and_operator,
+        and_operator,
+        or_or_operator,
+        or_operator,
+        question_operator,
+        becomes_operator,
+        not_operator,
+        iff_operator,
+        imply_operator,
+        globally_operator,
+        finally_operator,
+    ))(input)
+}
+
+// punctuations
+syntax! {comma_punctuation, ",", Token::Comma}
+syntax! {semicolon_punctuation, ";", Token::SemiColon}
+syntax! {colon_punctuation, ":", Token::Colon}
+syntax! {lparen_punctuation, "(", Token::LParen}
+syntax! {rparen_punctuation, ")", Token::RParen}
+// syntax! {lbrace_punctuation, "{", Token::LBrace}
+// syntax! {rbrace_punctuation, "}", Token::RBrace}
+// syntax! {lbracket_punctuation, "[", Token::LBracket}
+// syntax! {rbracket_punctuation, "]", Token::RBracket}
+
+pub fn lex_punctuations(input: &str) -> IResult<&str, Token> {
+    alt((
+        comma_punctuation,
+        semicolon_punctuation,
+        colon_punctuation,
+        lparen_punctuation,
+        rparen_punctuation,
+        // lbrace_punctuation,
+        // rbrace_punctuation,
+        // lbracket_punctuation,
+        // rbracket_punctuation,
+    ))(input)
+}
+
+/// Spot and ltl2ba write the constants as `1` and `0`.
+fn lex_integer(input: &str, dialect: Dialect) -> IResult<&str, Token> {
+    map(map_res(digit1, FromStr::from_str), |n| match (dialect, n) {
+        (Dialect::NuSmv, n) => Token::IntLiteral(n),
+        (_, 0) => Token::BoolLiteral(false),
+        (_, 1) => Token::BoolLiteral(true),
+        (_, n) => Token::IntLiteral(n),
+    })(input)
+}
+
+/// Spot double quotes atomic propositions that are not plain identifiers.
+fn lex_quoted_ident(input: &str) -> IResult<&str, Token> {
+    map(delimited(char('"'), is_not("\""), char('"')), |s: &str| {
+        Token::Ident(s.to_string())
+    })(input)
+}
+
+fn keyword(word: &str, dialect: Dialect) -> Option<Token> {
+    let token = match word {
+        "xor" => Token::Xor,
+        "X" => Token::LtlNext,
+        "U" => Token::LtlUntil,
+        "V" => Token::LtlRelease,
+        _ => match (dialect, word) {
+            (Dialect::NuSmv, "case") => Token::Case,
+            (Dialect::NuSmv, "esac") => Token::Esac,
+            (Dialect::NuSmv, "next") => Token::Next,
+            (Dialect::NuSmv, "boolean") => Token::Boolean,
+            (Dialect::NuSmv, "MODULE") => Token::Module,
+            (Dialect::NuSmv, "DEFINE") => Token::Define,
+            (Dialect::NuSmv, "IVAR") => Token::InputVar,
+            (Dialect::NuSmv, "VAR") => Token::LatchVar,
+            (Dialect::NuSmv, "CONSTANTS") => Token::Constant,
+            (Dialect::NuSmv, "INIT") => Token::Init,
+            (Dialect::NuSmv, "INVAR") => Token::Invariant,
+            (Dialect::NuSmv, "TRANS") => Token::Trans,
+            (Dialect::NuSmv, "FAIRNESS") => Token::Fairness,
+            (Dialect::NuSmv, "LTLSPEC") => Token::LtlSpec,
+            (Dialect::NuSmv, "TRUE") => Token::BoolLiteral(true),
+            (Dialect::NuSmv, "FALSE") => Token::BoolLiteral(false),
+            (Dialect::NuSmv, "O") => Token::LtlOnce,
+            (Dialect::NuSmv, "S") => Token::LtlSince,
+            (Dialect::NuSmv | Dialect::Spot, "F") => Token::LtlFinally,
+            (Dialect::NuSmv | Dialect::Spot, "G") => Token::LtlGlobally,
+            (Dialect::Spot, "R") => Token::LtlRelease,
+            (Dialect::Spot | Dialect::Ltl2ba, "true") => Token::BoolLiteral(true),
+            (Dialect::Spot | Dialect::Ltl2ba, "false") => Token::BoolLiteral(false),
+            _ => return None,
+        },
+    };
+    Some(token)
+}
+
+fn lex_reserved_ident(input: &str, dialect: Dialect) -> IResult<&str, Token> {
+    map(
+        recognize(pair(
+            alt((alpha1, tag("_"))),
+            many0(alt((alphanumeric1, tag("_"), tag(".")))),
+        )),
+        |s| keyword(s, dialect).unwrap_or_else(|| Token::Ident(s.to_string())),
+    )(input)
+}
+
+fn lex_token(input: &str, dialect: Dialect) -> IResult<&str, Token> {
+    alt((
+        lex_operator,
+        lex_punctuations,
+        |input| lex_integer(input, dialect),
+        lex_quoted_ident,
+        |input| lex_reserved_ident(input, dialect),
+    ))(input)
+}
+
+fn comment_line(input: &str) -> IResult<&str, Vec<Token>> {
+    tuple((many0(char(' ')), tag("--"), many0(anychar)))(input).map(|res| (res.0, Vec::new()))
+}
+
+fn lex_tokens_in_line(input: &str) -> IResult<&str, Vec<Token>> {
+    let (input, line) = is_not("\n")(input)?;
+    alt((
+        comment_line,
+        many1(delimited(
+            multispace0,
+            |input| lex_token(input, Dialect::NuSmv),
+            multispace0,
+        )),
+    ))(line)
+    .map(|(remain, token)| {
+        assert!(remain.is_empty());
+        (input, token)
+    })
+}
+
+/// Only the NuSMV dialect has `--` comments, the others are lexed as a single line.
+pub fn lex_tokens(
+    input: &str,
+    dialect: Dialect,
+) -> Result<Vec<Token>, nom::Err<nom::error::Error<&str>>> {
+    if dialect != Dialect::NuSmv {
+        let lex_token = |input| lex_token(input, dialect);
+        return many0(delimited(multispace0, lex_token, multispace0))(input).map(
+            |(remain, token)| {
+                assert!(remain.is_empty(), "unexpected {}", remain);
+                token
+            },
+        );
+    }
+    many0(delimited(multispace0, lex_tokens_in_line, multispace0))(input).map(|(remain, token)| {
+        assert!(remain.is_empty());
+        token.concat()
+    })
+}
@@ -0,0 +1,26 @@
+mod ast;
+mod bdd;
+mod dag;
+pub mod lexer;
+mod ltl;
+pub mod parser;
+mod printer;
+pub mod token;
+
+pub use ast::*;
+pub use dag::*;
+pub use printer::*;
+
+use lexer::lex_tokens;
+use parser::parse_expr;
+use token::Tokens;
+
+impl Expr {
+    /// Parses a whole expression written in `dialect`.
+    pub fn parse(input: &str, dialect: Dialect) -> Self {
+        let tokens = lex_tokens(input, dialect).unwrap();
+        let (rest, expr) = parse_expr(Tokens::new(&tokens)).unwrap();
+        assert!(rest.tok.is_empty(), "trailing tokens after {}", expr);
+        expr
+    }
+}
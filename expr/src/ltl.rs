@@ -0,0 +1,339 @@
+use crate::ast::{Expr, Infix, Prefix};
+
+fn prefix(op: Prefix, expr: Expr) -> Expr {
+    Expr::PrefixExpr(op, Box::new(expr))
+}
+
+fn infix(op: Infix, left: Expr, right: Expr) -> Expr {
+    Expr::InfixExpr(op, Box::new(left), Box::new(right))
+}
+
+fn negation(expr: Expr) -> Expr {
+    match expr {
+        Expr::LitExpr(lit) => Expr::LitExpr(!lit),
+        Expr::PrefixExpr(Prefix::Not, sub_expr) => *sub_expr,
+        _ => !expr,
+    }
+}
+
+fn next(op: Prefix, expr: Expr) -> Expr {
+    match expr {
+        Expr::LitExpr(_) => expr,
+        _ => prefix(op, expr),
+    }
+}
+
+fn globally(expr: Expr) -> Expr {
+    match expr {
+        Expr::LitExpr(_) => expr,
+        // G G a = G a, G F G a = F G a
+        Expr::PrefixExpr(Prefix::LtlGlobally, _) => expr,
+        Expr::PrefixExpr(Prefix::LtlFinally, ref sub_expr)
+            if matches!(**sub_expr, Expr::PrefixExpr(Prefix::LtlGlobally, _)) =>
+        {
+            expr
+        }
+        // G (a V b) = G b
+        Expr::InfixExpr(Infix::LtlRelease, _, right) => globally(*right),
+        _ => prefix(Prefix::LtlGlobally, expr),
+    }
+}
+
+fn finally(expr: Expr) -> Expr {
+    match expr {
+        Expr::LitExpr(_) => expr,
+        // F F a = F a, F G F a = G F a
+        Expr::PrefixExpr(Prefix::LtlFinally, _) => expr,
+        Expr::PrefixExpr(Prefix::LtlGlobally, ref sub_expr)
+            if matches!(**sub_expr, Expr::PrefixExpr(Prefix::LtlFinally, _)) =>
+        {
+            expr
+        }
+        // F (a U b) = F b
+        Expr::InfixExpr(Infix::LtlUntil, _, right) => finally(*right),
+        _ => prefix(Prefix::LtlFinally, expr),
+    }
+}
+
+fn until(left: Expr, right: Expr) -> Expr {
+    match (left, right) {
+        (_, Expr::LitExpr(lit)) => Expr::LitExpr(lit),
+        (Expr::LitExpr(false), right) => right,
+        (Expr::LitExpr(true), right) => finally(right),
+        (left, right) if left == right => left,
+        // X a U X b = X (a U b)
+        (Expr::PrefixExpr(Prefix::LtlNext, left), Expr::PrefixExpr(Prefix::LtlNext, right)) => {
+            next(Prefix::LtlNext, until(*left, *right))
+        }
+        (left, right) => infix(Infix::LtlUntil, left, right),
+    }
+}
+
+fn release(left: Expr, right: Expr) -> Expr {
+    match (left, right) {
+        (_, Expr::LitExpr(lit)) => Expr::LitExpr(lit),
+        (Expr::LitExpr(true), right) => right,
+        (Expr::LitExpr(false), right) => globally(right),
+        (left, right) if left == right => left,
+        // X a V X b = X (a V b)
+        (Expr::PrefixExpr(Prefix::LtlNext, left), Expr::PrefixExpr(Prefix::LtlNext, right)) => {
+            next(Prefix::LtlNext, release(*left, *right))
+        }
+        (left, right) => infix(Infix::LtlRelease, left, right),
+    }
+}
+
+fn flatten_junction(op: &Infix, expr: Expr, items: &mut Vec<Expr>) {
+    match expr {
+        Expr::InfixExpr(infix, left, right) if infix == *op => {
+            flatten_junction(op, *left, items);
+            flatten_junction(op, *right, items);
+        }
+        _ => items.push(expr),
+    }
+}
+
+fn junction_all(op: &Infix, items: Vec<Expr>) -> Expr {
+    let unit = Expr::LitExpr(*op == Infix::And);
+    items
+        .into_iter()
+        .reduce(|left, right| junction(op, left, right))
+        .unwrap_or(unit)
+}
+
+/// Strips the prefix chain `ops` from `expr`.
+fn strip<'a>(mut expr: &'a Expr, ops: &[Prefix]) -> Option<&'a Expr> {
+    for op in ops {
+        match expr {
+            Expr::PrefixExpr(prefix, sub_expr) if prefix == op => expr = sub_expr,
+            _ => return None,
+        }
+    }
+    Some(expr)
+}
+
+/// Merges the items under the prefix chain `ops` into one, `G a & G b = G (a & b)` and alike.
+fn merge_prefixs(op: &Infix, items: &mut Vec<Expr>, ops: &[Prefix]) {
+    let positions: Vec<usize> = (0..items.len())
+        .filter(|i| strip(&items[*i], ops).is_some())
+        .collect();
+    if positions.len() < 2 {
+        return;
+    }
+    let subs = positions
+        .iter()
+        .map(|i| strip(&items[*i], ops).unwrap().clone())
+        .collect();
+    let mut expr = junction_all(op, subs);
+    for prefix in ops.iter().rev() {
+        expr = match prefix {
+            Prefix::LtlGlobally => globally(expr),
+            Prefix::LtlFinally => finally(expr),
+            _ => next(prefix.clone(), expr),
+        };
+    }
+    for i in positions[1..].iter().rev() {
+        items.remove(*i);
+    }
+    items[positions[0]] = expr;
+}
+
+/// Merges two untils or releases sharing an operand, `(a U b) & (c U b) = (a & c) U b`,
+/// `(a U b) | (a U c) = a U (b | c)` and the duals for release.
+fn merge_binary(op: &Infix, items: &mut Vec<Expr>) -> bool {
+    for i in 0..items.len() {
+        for j in i + 1..items.len() {
+            let (Expr::InfixExpr(x, a, b), Expr::InfixExpr(y, c, d)) = (&items[i], &items[j])
+            else {
+                continue;
+            };
+            if x != y {
+                continue;
+            }
+            let share_right = match (op, x) {
+                (Infix::And, Infix::LtlUntil) | (Infix::Or, Infix::LtlRelease) => true,
+                (Infix::Or, Infix::LtlUntil) | (Infix::And, Infix::LtlRelease) => false,
+                _ => continue,
+            };
+            let merged = if share_right && b == d {
+                let left = junction(op, *a.clone(), *c.clone());
+                (left, *b.clone())
+            } else if !share_right && a == c {
+                let right = junction(op, *b.clone(), *d.clone());
+                (*a.clone(), right)
+            } else {
+                continue;
+            };
+            let merged = match x {
+                Infix::LtlUntil => until(merged.0, merged.1),
+                _ => release(merged.0, merged.1),
+            };
+            items.remove(j);
+            items[i] = merged;
+            return true;
+        }
+    }
+    false
+}
+
+/// Conjunction or disjunction of `left` and `right` as `op`.
+fn junction(op: &Infix, left: Expr, right: Expr) -> Expr {
+    let zero = Expr::LitExpr(*op == Infix::Or);
+    let mut flattened = Vec::new();
+    flatten_junction(op, left, &mut flattened);
+    flatten_junction(op, right, &mut flattened);
+    let mut items: Vec<Expr> = Vec::new();
+    for item in flattened {
+        match item {
+            Expr::LitExpr(_) if item == zero => return zero,
+            Expr::LitExpr(_) => continue,
+            _ if items.contains(&item) => continue,
+            _ if items.contains(&negation(item.clone())) => return zero,
+            _ => items.push(item),
+        }
+    }
+    let len = items.len();
+    match op {
+        Infix::And => {
+            merge_prefixs(op, &mut items, &[Prefix::LtlGlobally]);
+            merge_prefixs(op, &mut items, &[Prefix::LtlFinally, Prefix::LtlGlobally]);
+        }
+        _ => {
+            merge_prefixs(op, &mut items, &[Prefix::LtlFinally]);
+            merge_prefixs(op, &mut items, &[Prefix::LtlGlobally, Prefix::LtlFinally]);
+        }
+    }
+    merge_prefixs(op, &mut items, &[Prefix::LtlNext]);
+    while merge_binary(op, &mut items) {}
+    if items.len() < len {
+        // a merged item may fold further
+        return junction_all(op, items);
+    }
+    let mut items = items.into_iter();
+    let first = items.next().unwrap_or(Expr::LitExpr(*op == Infix::And));
+    items.fold(first, |left, right| infix(op.clone(), left, right))
+}
+
+impl Expr {
+    /// Whether the expression has neither temporal operators nor `next`.
+    pub fn is_propositional(&self) -> bool {
+        match self {
+            Expr::Ident(_) | Expr::LitExpr(_) => true,
+            Expr::PrefixExpr(Prefix::Not, sub_expr) => sub_expr.is_propositional(),
+            Expr::PrefixExpr(..) => false,
+            Expr::InfixExpr(
+                Infix::And | Infix::Or | Infix::Xor | Infix::Imply | Infix::Iff,
+                left,
+                right,
+            ) => left.is_propositional() && right.is_propositional(),
+            Expr::InfixExpr(..) => false,
+            Expr::CaseExpr(case_expr) => case_expr
+                .branchs
+                .iter()
+                .all(|(cond, res)| cond.is_propositional() && res.is_propositional()),
+        }
+    }
+
+    /// Negation normal form of the expression, negated if `negate`. Negations stay above the
+    /// past operators since their duals are missing.
+    fn nnf(&self, negate: bool) -> Expr {
+        match self {
+            Expr::Ident(_) if negate => !self.clone(),
+            Expr::Ident(_) => self.clone(),
+            Expr::LitExpr(lit) => Expr::LitExpr(lit ^ negate),
+            Expr::PrefixExpr(op, sub_expr) => match op {
+                Prefix::Not => sub_expr.nnf(!negate),
+                Prefix::Next | Prefix::LtlNext => next(op.clone(), sub_expr.nnf(negate)),
+                Prefix::LtlGlobally if negate => finally(sub_expr.nnf(true)),
+                Prefix::LtlGlobally => globally(sub_expr.nnf(false)),
+                Prefix::LtlFinally if negate => globally(sub_expr.nnf(true)),
+                Prefix::LtlFinally => finally(sub_expr.nnf(false)),
+                Prefix::LtlOnce => {
+                    let expr = prefix(Prefix::LtlOnce, sub_expr.nnf(false));
+                    if negate {
+                        negation(expr)
+                    } else {
+                        expr
+                    }
+                }
+            },
+            Expr::InfixExpr(op, left, right) => match op {
+                Infix::And | Infix::Or => {
+                    let op = match (op, negate) {
+                        (Infix::And, false) | (Infix::Or, true) => Infix::And,
+                        _ => Infix::Or,
+                    };
+                    junction(&op, left.nnf(negate), right.nnf(negate))
+                }
+                Infix::Imply if negate => junction(&Infix::And, left.nnf(false), right.nnf(true)),
+                Infix::Imply => junction(&Infix::Or, left.nnf(true), right.nnf(false)),
+                Infix::Xor | Infix::Iff => {
+                    let iff = (*op == Infix::Iff) != negate;
+                    if self.is_propositional() {
+                        let op = if iff { Infix::Iff } else { Infix::Xor };
+                        return infix(op, left.nnf(false), right.nnf(false));
+                    }
+                    let both = junction(&Infix::And, left.nnf(false), right.nnf(!iff));
+                    let neither = junction(&Infix::And, left.nnf(true), right.nnf(iff));
+                    junction(&Infix::Or, both, neither)
+                }
+                Infix::LtlUntil if negate => release(left.nnf(true), right.nnf(true)),
+                Infix::LtlUntil => until(left.nnf(false), right.nnf(false)),
+                Infix::LtlRelease if negate => until(left.nnf(true), right.nnf(true)),
+                Infix::LtlRelease => release(left.nnf(false), right.nnf(false)),
+                Infix::LtlSince => {
+                    let expr = infix(Infix::LtlSince, left.nnf(false), right.nnf(false));
+                    if negate {
+                        negation(expr)
+                    } else {
+                        expr
+                    }
+                }
+            },
+            Expr::CaseExpr(_) if self.is_propositional() => {
+                if negate {
+                    !self.clone()
+                } else {
+                    self.clone()
+                }
+            }
+            Expr::CaseExpr(case_expr) => case_expr.to_propositional().nnf(negate),
+        }
+    }
+
+    /// Rewrites an LTL formula into negation normal form, folding constants and applying the
+    /// rules of Somenzi and Bloem and of Etessami and Holzmann on the way up.
+    pub fn simplify_ltl(&self) -> Expr {
+        self.nnf(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Dialect, Expr};
+
+    fn parse(expr: &str) -> Expr {
+        Expr::parse(expr, Dialect::NuSmv)
+    }
+
+    #[test]
+    fn test_simplify_ltl() {
+        let cases = [
+            ("!(TRUE & G a -> TRUE & G F b)", "G a & F G !b"),
+            ("!(a U b) | FALSE", "!a V !b"),
+            ("G a & c & G (b & TRUE)", "G (a & b) & c"),
+            ("F G a & F G b", "F G (a & b)"),
+            ("G F a | G F b | F c | F d", "G F (a | b) | F (c | d)"),
+            ("(a U c) & (b U c)", "(a & b) U c"),
+            ("X a | X !a | b", "TRUE"),
+            ("F (a U b) & G (a V G c)", "F b & G c"),
+            (
+                "!(a <-> b) & !(G a <-> b)",
+                "(a xor b) & (G a & !b | F !a & b)",
+            ),
+        ];
+        for (expr, simplified) in cases {
+            assert_eq!(parse(expr).simplify_ltl(), parse(simplified), "{}", expr);
+        }
+    }
+}
@@ -1,33 +1,39 @@
-use super::token::*;
-use crate::{Expr, Infix, Prefix};
+use crate::{
+    ast::{CaseExpr, Expr, Infix, Prefix},
+    token::*,
+};
 use nom::{
     branch::alt,
     bytes::complete::take,
     combinator::map,
     error::{Error, ErrorKind},
     error_position,
-    sequence::delimited,
+    multi::many1,
+    sequence::{delimited, tuple},
     IResult,
 };
 
+/// Binding strength of the infix operators as in NuSMV, `->` groups to the right and the
+/// others to the left.
 #[derive(PartialEq, PartialOrd, Debug, Clone)]
 pub enum Precedence {
     Lowest,
-    And,
-    Or,
     Imply,
     Iff,
-    LtlUntil,
-    LtlSince,
+    Or,
+    And,
+    Ltl,
 }
 
 fn parse_infix_op(input: Tokens) -> IResult<Tokens, (Precedence, Infix)> {
     let (input, op) = alt((
         and_tag,
+        xor_tag,
         or_tag,
         imply_tag,
         iff_tag,
         ltl_until_tag,
+        ltl_release_tag,
         ltl_since_tag,
     ))(input)?;
     Ok((
@@ -35,22 +41,29 @@ fn parse_infix_op(input: Tokens) -> IResult<Tokens, (Precedence, Infix)> {
         match op {
             Token::And => (Precedence::And, Infix::And),
             Token::Or => (Precedence::Or, Infix::Or),
+            Token::Xor => (Precedence::Or, Infix::Xor),
             Token::Imply => (Precedence::Imply, Infix::Imply),
             Token::Iff => (Precedence::Iff, Infix::Iff),
-            Token::LtlUntil => (Precedence::LtlUntil, Infix::LtlUntil),
-            Token::LtlSince => (Precedence::LtlSince, Infix::LtlSince),
+            Token::LtlUntil => (Precedence::Ltl, Infix::LtlUntil),
+            Token::LtlRelease => (Precedence::Ltl, Infix::LtlRelease),
+            Token::LtlSince => (Precedence::Ltl, Infix::LtlSince),
             _ => panic!(),
         },
     ))
 }
 
-fn parse_ident(input: Tokens) -> IResult<Tokens, String> {
+pub fn parse_ident(input: Tokens) -> IResult<Tokens, String> {
     let (i1, t1) = take(1usize)(input)?;
     if t1.tok.is_empty() {
         Err(nom::Err::Error(Error::new(input, ErrorKind::Tag)))
     } else {
         match t1.tok[0].clone() {
-            Token::Ident(name) => Ok((i1, name.replace('.', "_"))),
+            Token::Ident(mut name) => {
+                while let Some(n) = name.strip_prefix('_') {
+                    name = n.to_string();
+                }
+                Ok((i1, name.replace('.', "_")))
+            }
             _ => Err(nom::Err::Error(Error::new(input, ErrorKind::Tag))),
         }
     }
@@ -77,6 +90,17 @@ fn parse_paren_expr(input: Tokens) -> IResult<Tokens, Expr> {
     delimited(lparen_tag, parse_expr, rparen_tag)(input)
 }
 
+fn parse_case_condition(input: Tokens) -> IResult<Tokens, (Expr, Expr)> {
+    let (input, (cond, _, then, _)) =
+        tuple((parse_expr, colon_tag, parse_expr, semicolon_tag))(input)?;
+    Ok((input, (cond, then)))
+}
+
+fn parse_case_expr(input: Tokens) -> IResult<Tokens, Expr> {
+    let (input, branchs) = delimited(case_tag, many1(parse_case_condition), esac_tag)(input)?;
+    Ok((input, Expr::CaseExpr(CaseExpr { branchs })))
+}
+
 fn parse_prefix_expr(input: Tokens) -> IResult<Tokens, Expr> {
     let (i1, op) = alt((
         not_tag,
@@ -103,6 +127,7 @@ fn parse_atom_expr(input: Tokens) -> IResult<Tokens, Expr> {
         parse_lit_expr,
         parse_ident_expr,
         parse_paren_expr,
+        parse_case_expr,
         parse_prefix_expr,
     ))(input)
 }
@@ -120,6 +145,10 @@ fn parse_infix_expr(
 fn go_parse_pratt_expr(input: Tokens, precedence: Precedence, left: Expr) -> IResult<Tokens, Expr> {
     match parse_infix_op(input) {
         Ok((i1, (peek_precedence, op))) if precedence < peek_precedence => {
+            let peek_precedence = match op {
+                Infix::Imply => Precedence::Lowest,
+                _ => peek_precedence,
+            };
             let (i2, left2) = parse_infix_expr(i1, left, op, peek_precedence)?;
             go_parse_pratt_expr(i2, precedence, left2)
         }
@@ -132,12 +161,70 @@ fn parse_pratt_expr(input: Tokens, precedence: Precedence) -> IResult<Tokens, Ex
     go_parse_pratt_expr(i1, precedence, left)
 }
 
-fn parse_expr(input: Tokens) -> IResult<Tokens, Expr> {
+pub fn parse_expr(input: Tokens) -> IResult<Tokens, Expr> {
     parse_pratt_expr(input, Precedence::Lowest)
 }
 
-pub fn parse_tokens(input: Tokens) -> Result<Expr, nom::Err<nom::error::Error<Tokens<'_>>>> {
-    let (input, expr) = parse_expr(input)?;
-    assert!(input.tok.is_empty());
-    Ok(expr)
+#[cfg(test)]
+mod tests {
+    use crate::{Dialect, Expr, Infix};
+
+    fn parse(expr: &str) -> Expr {
+        Expr::parse(expr, Dialect::NuSmv)
+    }
+
+    fn infix(op: Infix, left: Expr, right: Expr) -> Expr {
+        Expr::InfixExpr(op, Box::new(left), Box::new(right))
+    }
+
+    fn ident(ident: &str) -> Expr {
+        Expr::Ident(ident.to_string())
+    }
+
+    #[test]
+    fn test_precedence() {
+        let (a, b, c) = (ident("a"), ident("b"), ident("c"));
+        let a_and_b = infix(Infix::And, a.clone(), b.clone());
+        assert_eq!(
+            parse("a & b | c"),
+            infix(Infix::Or, a_and_b.clone(), c.clone())
+        );
+        assert_eq!(
+            parse("a | b & c"),
+            infix(
+                Infix::Or,
+                a.clone(),
+                infix(Infix::And, b.clone(), c.clone())
+            )
+        );
+        // xor shares the level of or and groups to the left
+        assert_eq!(
+            parse("a xor b | c"),
+            infix(
+                Infix::Or,
+                infix(Infix::Xor, a.clone(), b.clone()),
+                c.clone()
+            )
+        );
+        assert_eq!(
+            parse("a -> b -> c"),
+            infix(
+                Infix::Imply,
+                a.clone(),
+                infix(Infix::Imply, b.clone(), c.clone())
+            )
+        );
+        assert_eq!(
+            parse("a <-> b -> c"),
+            infix(
+                Infix::Imply,
+                infix(Infix::Iff, a.clone(), b.clone()),
+                c.clone()
+            )
+        );
+        assert_eq!(
+            parse("a U b & c"),
+            infix(Infix::And, infix(Infix::LtlUntil, a, b), c)
+        );
+    }
 }
@@ -0,0 +1,310 @@
+use crate::ast::{CaseExpr, Expr, Infix, Prefix};
+use std::{
+    borrow::Cow,
+    fmt::{Display, Formatter, Result},
+};
+
+/// Concrete syntax of a parsed or printed expression.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    /// The syntax of SMV files, it is parsed back by this crate.
+    NuSmv,
+    /// Input of the Spot tools such as `ltl2tgba`.
+    Spot,
+    /// Input of `ltl2ba`.
+    Ltl2ba,
+}
+
+impl Dialect {
+    fn prefix(&self, prefix: &Prefix) -> &'static str {
+        match (self, prefix) {
+            (_, Prefix::Not) => "!",
+            (Dialect::NuSmv, Prefix::Next) => "next",
+            (Dialect::NuSmv, Prefix::LtlOnce) => "O",
+            (_, Prefix::LtlOnce) => panic!("past operator O has no {:?} syntax", self),
+            (_, Prefix::Next) | (_, Prefix::LtlNext) => "X",
+            (Dialect::Ltl2ba, Prefix::LtlGlobally) => "[]",
+            (Dialect::Ltl2ba, Prefix::LtlFinally) => "<>",
+            (_, Prefix::LtlGlobally) => "G",
+            (_, Prefix::LtlFinally) => "F",
+        }
+    }
+
+    fn infix(&self, infix: &Infix) -> &'static str {
+        match (self, infix) {
+            (Dialect::Ltl2ba, Infix::And) => "&&",
+            (Dialect::Ltl2ba, Infix::Or) => "||",
+            (Dialect::Ltl2ba, Infix::Xor) => unreachable!(),
+            (_, Infix::And) => "&",
+            (_, Infix::Or) => "|",
+            (_, Infix::Xor) => "xor",
+            (_, Infix::Imply) => "->",
+            (_, Infix::Iff) => "<->",
+            (_, Infix::LtlUntil) => "U",
+            (Dialect::Spot, Infix::LtlRelease) => "R",
+            (_, Infix::LtlRelease) => "V",
+            (Dialect::NuSmv, Infix::LtlSince) => "S",
+            (_, Infix::LtlSince) => panic!("past operator S has no {:?} syntax", self),
+        }
+    }
+
+    /// Binding strength of `infix`, a larger one binds tighter. Spot and ltl2ba put `<->` on
+    /// the level of `->`.
+    fn binding(&self, infix: &Infix) -> usize {
+        match infix {
+            Infix::Imply => 1,
+            Infix::Iff if *self == Dialect::NuSmv => 2,
+            Infix::Iff => 1,
+            Infix::Or | Infix::Xor => 3,
+            Infix::And => 4,
+            Infix::LtlUntil | Infix::LtlRelease | Infix::LtlSince => 5,
+        }
+    }
+
+    /// `expr` with the operators the dialect lacks at its top rewritten, a case expression
+    /// outside of NuSMV and xor in ltl2ba.
+    fn view<'a>(&self, expr: &'a Expr) -> Cow<'a, Expr> {
+        match expr {
+            Expr::CaseExpr(case_expr) if *self != Dialect::NuSmv => {
+                Cow::Owned(case_expr.to_propositional())
+            }
+            Expr::InfixExpr(Infix::Xor, left, right) if *self == Dialect::Ltl2ba => {
+                Cow::Owned(!Expr::InfixExpr(Infix::Iff, left.clone(), right.clone()))
+            }
+            _ => Cow::Borrowed(expr),
+        }
+    }
+
+    /// Whether the operand `child` of `parent` needs parentheses. `&`, `|` and `xor` group to
+    /// the left, `->` to the right, chains of the other operators are always parenthesized.
+    fn infix_paren(&self, parent: &Infix, child: &Expr, left: bool) -> bool {
+        match child {
+            Expr::InfixExpr(infix, _, _) => {
+                let (parent_binding, binding) = (self.binding(parent), self.binding(infix));
+                let assoc = match infix {
+                    Infix::And | Infix::Or | Infix::Xor => left,
+                    Infix::Imply => !left,
+                    _ => false,
+                };
+                binding < parent_binding || binding == parent_binding && !(infix == parent && assoc)
+            }
+            // NuSMV reads `!a U b` as `!(a U b)`, and `G !a U b` as `G !(a U b)`
+            Expr::PrefixExpr(prefix, _) => {
+                *self == Dialect::NuSmv && self.binding(parent) == 5 && *prefix != Prefix::Next
+            }
+            _ => false,
+        }
+    }
+
+    fn write_ident(&self, f: &mut Formatter<'_>, ident: &str) -> Result {
+        // Spot splits `Fa` into `F a`
+        let quote = *self == Dialect::Spot
+            && (ident.starts_with(['F', 'G', 'X', 'U', 'R', 'W', 'M'])
+                || ident == "true"
+                || ident == "false");
+        if quote {
+            write!(f, "\"{}\"", ident)
+        } else {
+            write!(f, "{}", ident)
+        }
+    }
+
+    fn write_operand(&self, f: &mut Formatter<'_>, expr: &Expr, paren: bool) -> Result {
+        if paren {
+            write!(f, "(")?;
+            self.write(f, expr)?;
+            write!(f, ")")
+        } else {
+            self.write(f, expr)
+        }
+    }
+
+    fn write(&self, f: &mut Formatter<'_>, expr: &Expr) -> Result {
+        let expr = self.view(expr);
+        match expr.as_ref() {
+            Expr::Ident(ident) => self.write_ident(f, ident),
+            Expr::LitExpr(lit) => {
+                let lit = match (self, lit) {
+                    (Dialect::NuSmv, true) => "TRUE",
+                    (Dialect::NuSmv, false) => "FALSE",
+                    (_, true) => "true",
+                    (_, false) => "false",
+                };
+                write!(f, "{}", lit)
+            }
+            Expr::PrefixExpr(Prefix::Next, sub_expr) if *self == Dialect::NuSmv => {
+                write!(f, "next")?;
+                self.write_operand(f, sub_expr, true)
+            }
+            Expr::PrefixExpr(prefix, sub_expr) => {
+                write!(f, "{}", self.prefix(prefix))?;
+                if *prefix != Prefix::Not {
+                    write!(f, " ")?;
+                }
+                let paren = matches!(self.view(sub_expr).as_ref(), Expr::InfixExpr(..));
+                self.write_operand(f, sub_expr, paren)
+            }
+            Expr::InfixExpr(infix, left, right) => {
+                let paren = self.infix_paren(infix, &self.view(left), true);
+                self.write_operand(f, left, paren)?;
+                write!(f, " {} ", self.infix(infix))?;
+                let paren = self.infix_paren(infix, &self.view(right), false);
+                self.write_operand(f, right, paren)
+            }
+            Expr::CaseExpr(case_expr) => {
+                write!(f, "case")?;
+                for (cond, res) in case_expr.branchs.iter() {
+                    write!(f, " ")?;
+                    self.write(f, cond)?;
+                    write!(f, " : ")?;
+                    self.write(f, res)?;
+                    write!(f, ";")?;
+                }
+                write!(f, " esac")
+            }
+        }
+    }
+}
+
+impl CaseExpr {
+    /// The case expression as `c0 & r0 | !c0 & (c1 & r1 | ...)`, false if no branch applies.
+    pub(crate) fn to_propositional(&self) -> Expr {
+        let mut branchs = self.branchs.iter().rev();
+        let Some((cond, res)) = branchs.next() else {
+            return Expr::LitExpr(false);
+        };
+        let mut expr = cond.clone() & res.clone();
+        for (cond, res) in branchs {
+            expr = (cond.clone() & res.clone()) | (!cond.clone() & expr);
+        }
+        expr
+    }
+}
+
+/// An expression printed in a dialect with only the parentheses it needs.
+pub struct DialectExpr<'a> {
+    expr: &'a Expr,
+    dialect: Dialect,
+}
+
+impl Display for DialectExpr<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        self.dialect.write(f, self.expr)
+    }
+}
+
+impl Expr {
+    pub fn display(&self, dialect: Dialect) -> DialectExpr<'_> {
+        DialectExpr {
+            expr: self,
+            dialect,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CaseExpr, Dialect, Expr, Infix, Prefix};
+
+    fn parse(expr: &str) -> Expr {
+        Expr::parse(expr, Dialect::NuSmv)
+    }
+
+    /// A pseudo random expression of the given depth over `a`, `b` and `c`.
+    fn random_expr(seed: &mut u64, depth: usize) -> Expr {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 7;
+        *seed ^= *seed << 17;
+        let choice = *seed as usize % 18;
+        if depth == 0 || choice < 3 {
+            return match choice % 4 {
+                0 => Expr::LitExpr(choice < 8),
+                1 => Expr::Ident("a".to_string()),
+                2 => Expr::Ident("b".to_string()),
+                _ => Expr::Ident("c".to_string()),
+            };
+        }
+        let prefixs = [
+            Prefix::Not,
+            Prefix::Next,
+            Prefix::LtlGlobally,
+            Prefix::LtlFinally,
+            Prefix::LtlNext,
+            Prefix::LtlOnce,
+        ];
+        let infixs = [
+            Infix::And,
+            Infix::Or,
+            Infix::Xor,
+            Infix::Imply,
+            Infix::Iff,
+            Infix::LtlUntil,
+            Infix::LtlRelease,
+            Infix::LtlSince,
+        ];
+        match choice {
+            3..=8 => Expr::PrefixExpr(
+                prefixs[choice - 3].clone(),
+                Box::new(random_expr(seed, depth - 1)),
+            ),
+            9..=16 => Expr::InfixExpr(
+                infixs[choice - 9].clone(),
+                Box::new(random_expr(seed, depth - 1)),
+                Box::new(random_expr(seed, depth - 1)),
+            ),
+            _ => Expr::InfixExpr(
+                Infix::And,
+                Box::new(random_expr(seed, depth - 1)),
+                Box::new(Expr::CaseExpr(CaseExpr {
+                    branchs: vec![
+                        (random_expr(seed, depth - 1), random_expr(seed, depth - 1)),
+                        (Expr::LitExpr(true), random_expr(seed, depth - 1)),
+                    ],
+                })),
+            ),
+        }
+    }
+
+    #[test]
+    fn test_minimal_parentheses() {
+        let expr = parse("(a & b) | (c -> (a -> b))");
+        assert_eq!(expr.to_string(), "a & b | (c -> a -> b)");
+        let expr = parse("((a | b) & !(c U b)) U (G (F a))");
+        assert_eq!(expr.to_string(), "((a | b) & !(c U b)) U (G F a)");
+        assert_eq!(
+            expr.display(Dialect::Spot).to_string(),
+            "((a | b) & !(c U b)) U G F a"
+        );
+        assert_eq!(
+            expr.display(Dialect::Ltl2ba).to_string(),
+            "((a || b) && !(c U b)) U [] <> a"
+        );
+        for dialect in [Dialect::Spot, Dialect::Ltl2ba] {
+            let printed = expr.display(dialect).to_string();
+            assert_eq!(Expr::parse(&printed, dialect), expr);
+        }
+        let expr = parse("(!a) U (b xor c)");
+        assert_eq!(expr.to_string(), "(!a) U (b xor c)");
+        assert_eq!(expr.display(Dialect::Spot).to_string(), "!a U (b xor c)");
+        assert_eq!(expr.display(Dialect::Ltl2ba).to_string(), "!a U !(b <-> c)");
+        let expr = parse("case a : next(b); TRUE : Fc; esac");
+        assert_eq!(expr.to_string(), "case a : next(b); TRUE : Fc; esac");
+        assert_eq!(
+            expr.display(Dialect::Spot).to_string(),
+            "a & X b | !a & (true & \"Fc\")"
+        );
+        let label = Expr::parse("(!(p0)) && (p1 || \"Fc\")", Dialect::Spot);
+        assert_eq!(label, parse("!p0 & (p1 | Fc)"));
+        assert_eq!(Expr::parse("(1)", Dialect::Spot), Expr::LitExpr(true));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut seed = 0x2545f4914f6cdd1d;
+        for _ in 0..1000 {
+            let expr = random_expr(&mut seed, 5);
+            let printed = expr.to_string();
+            assert_eq!(parse(&printed), expr, "{}", printed);
+        }
+    }
+}
@@ -0,0 +1,327 @@
+use crate::FsmBdd;
+use bdds::{Bdd, BddManager};
+use std::ops::{BitAnd, BitOr, BitXor, Not};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FairCycleAlgorithm {
+    EmersonLei,
+    EmersonLeiChaining,
+    Owcty,
+    Lockstep,
+    XieBeerel,
+}
+
+/// A symbolic graph the fair-cycle algorithms run on.
+pub trait FairGraph {
+    type Set: Clone + PartialEq;
+
+    fn empty(&self) -> Self::Set;
+
+    fn and(&self, a: &Self::Set, b: &Self::Set) -> Self::Set;
+
+    fn or(&self, a: &Self::Set, b: &Self::Set) -> Self::Set;
+
+    fn diff(&self, a: &Self::Set, b: &Self::Set) -> Self::Set;
+
+    fn is_empty(&self, a: &Self::Set) -> bool;
+
+    fn pre_image(&self, a: &Self::Set) -> Self::Set;
+
+    fn post_image(&self, a: &Self::Set) -> Self::Set;
+
+    fn justice(&self) -> Vec<Self::Set>;
+
+    /// A single state of the non-empty set `a`.
+    fn pick(&self, a: &Self::Set) -> Self::Set;
+
+    /// States in `constrain` reachable from `from` in zero or more steps inside `constrain`.
+    fn reachable(&self, from: &Self::Set, forward: bool, constrain: &Self::Set) -> Self::Set {
+        let mut reach = self.and(from, constrain);
+        let mut frontier = reach.clone();
+        loop {
+            let image = if forward {
+                self.post_image(&frontier)
+            } else {
+                self.pre_image(&frontier)
+            };
+            frontier = self.diff(&self.and(&image, constrain), &reach);
+            if self.is_empty(&frontier) {
+                break reach;
+            }
+            reach = self.or(&reach, &frontier);
+        }
+    }
+}
+
+/// Picks one minterm over the state variables out of `bdd`.
+pub fn pick_state<BM: BddManager>(manager: &BM, bdd: &BM::Bdd) -> BM::Bdd
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    let nodes = bdd.export();
+    let mut assign = Vec::new();
+    let mut node = nodes.root;
+    while node > 1 {
+        let (var, low, high) = nodes.nodes[node - 2];
+        if low != 0 {
+            node = low;
+        } else {
+            assign.push(var);
+            node = high;
+        }
+    }
+    assert!(node == 1);
+    let mut res = manager.constant(true);
+    for var in manager.state_vars() {
+        if assign.contains(&var) {
+            res &= manager.ith_var(var);
+        } else {
+            res &= !manager.ith_var(var);
+        }
+    }
+    res
+}
+
+fn strict_pre_reachable<G: FairGraph>(graph: &G, target: &G::Set, constrain: &G::Set) -> G::Set {
+    let pre = graph.and(&graph.pre_image(target), constrain);
+    graph.reachable(&pre, false, constrain)
+}
+
+fn emerson_lei<G: FairGraph>(graph: &G, constrain: &G::Set, chaining: bool) -> G::Set {
+    let justice = graph.justice();
+    let mut res = constrain.clone();
+    loop {
+        let mut new = res.clone();
+        for fair in justice.iter() {
+            // chaining uses the states already removed in this iteration
+            let from = if chaining { &new } else { &res };
+            let fair = graph.and(fair, from);
+            new = graph.and(&new, &strict_pre_reachable(graph, &fair, constrain));
+        }
+        if new == res {
+            break res;
+        }
+        res = new;
+    }
+}
+
+fn owcty<G: FairGraph>(graph: &G, constrain: &G::Set) -> G::Set {
+    let justice = graph.justice();
+    let mut res = constrain.clone();
+    loop {
+        let old = res.clone();
+        for fair in justice.iter() {
+            res = graph.reachable(&graph.and(fair, &res), false, &res);
+        }
+        loop {
+            let trimmed = graph.and(&res, &graph.pre_image(&res));
+            if trimmed == res {
+                break;
+            }
+            res = trimmed;
+        }
+        if res == old {
+            break res;
+        }
+    }
+}
+
+fn is_fair_scc<G: FairGraph>(graph: &G, scc: &G::Set, justice: &[G::Set]) -> bool {
+    !graph.is_empty(&graph.and(scc, &graph.pre_image(scc)))
+        && justice
+            .iter()
+            .all(|fair| !graph.is_empty(&graph.and(scc, fair)))
+}
+
+/// Decomposes `constrain` into SCCs until a fair one is found. With `lockstep` the forward and
+/// backward searches from the seed are interleaved and the one that converges first bounds the
+/// other, otherwise the SCC is the backward set of the seed inside its forward set.
+fn scc_decomposition<G: FairGraph>(graph: &G, constrain: &G::Set, lockstep: bool) -> G::Set {
+    let justice = graph.justice();
+    let mut stack = vec![constrain.clone()];
+    while let Some(states) = stack.pop() {
+        if graph.is_empty(&states) {
+            continue;
+        }
+        let seed = graph.pick(&states);
+        let (converged, scc) = if lockstep {
+            let (mut forward, mut backward) = (seed.clone(), seed.clone());
+            let (mut forward_frontier, mut backward_frontier) = (seed.clone(), seed.clone());
+            while !graph.is_empty(&forward_frontier) && !graph.is_empty(&backward_frontier) {
+                let image = graph.and(&graph.post_image(&forward_frontier), &states);
+                forward_frontier = graph.diff(&image, &forward);
+                forward = graph.or(&forward, &forward_frontier);
+                let image = graph.and(&graph.pre_image(&backward_frontier), &states);
+                backward_frontier = graph.diff(&image, &backward);
+                backward = graph.or(&backward, &backward_frontier);
+            }
+            if graph.is_empty(&forward_frontier) {
+                let scc = graph.reachable(&graph.and(&backward, &forward), false, &forward);
+                (forward, scc)
+            } else {
+                let scc = graph.reachable(&graph.and(&forward, &backward), true, &backward);
+                (backward, scc)
+            }
+        } else {
+            let forward = graph.reachable(&seed, true, &states);
+            let scc = graph.reachable(&seed, false, &forward);
+            (forward, scc)
+        };
+        if is_fair_scc(graph, &scc, &justice) {
+            return scc;
+        }
+        stack.push(graph.diff(&converged, &scc));
+        stack.push(graph.diff(&states, &converged));
+    }
+    graph.empty()
+}
+
+/// States in `constrain` related to fair cycles inside `constrain`, the result is empty iff
+/// there is no fair cycle. The fixpoint algorithms return every state with a fair path, the
+/// SCC decompositions stop at the first fair SCC and return it.
+pub fn fair_cycle<G: FairGraph>(
+    graph: &G,
+    constrain: &G::Set,
+    algorithm: FairCycleAlgorithm,
+) -> G::Set {
+    match algorithm {
+        FairCycleAlgorithm::EmersonLei => emerson_lei(graph, constrain, false),
+        FairCycleAlgorithm::EmersonLeiChaining => emerson_lei(graph, constrain, true),
+        FairCycleAlgorithm::Owcty => owcty(graph, constrain),
+        FairCycleAlgorithm::Lockstep => scc_decomposition(graph, constrain, true),
+        FairCycleAlgorithm::XieBeerel => scc_decomposition(graph, constrain, false),
+    }
+}
+
+/// The stem and the loop of a lasso, the last loop state has the first one as successor.
+pub type Lasso<S> = (Vec<S>, Vec<S>);
+
+/// A shortest path of single states from `from` to `to` inside `constrain`, with at least
+/// `min_steps` steps. The first state is picked from `from`.
+fn shortest_path<G: FairGraph>(
+    graph: &G,
+    from: &G::Set,
+    to: &G::Set,
+    constrain: &G::Set,
+    min_steps: usize,
+) -> Option<Vec<G::Set>> {
+    let mut rings = vec![from.clone()];
+    let mut reach = graph.empty();
+    loop {
+        let last = rings.last().unwrap();
+        if rings.len() > min_steps && !graph.is_empty(&graph.and(last, to)) {
+            break;
+        }
+        let image = graph.and(&graph.post_image(last), constrain);
+        let frontier = graph.diff(&image, &reach);
+        if graph.is_empty(&frontier) {
+            return None;
+        }
+        reach = graph.or(&reach, &frontier);
+        rings.push(frontier);
+    }
+    let mut state = graph.pick(&graph.and(rings.last().unwrap(), to));
+    let mut path = vec![state.clone()];
+    for ring in rings.iter().rev().skip(1) {
+        state = graph.pick(&graph.and(ring, &graph.pre_image(&state)));
+        path.push(state.clone());
+    }
+    path.reverse();
+    Some(path)
+}
+
+/// A lasso from `init` through the reachable states `reach` whose loop visits every justice
+/// set inside `fair`, the result of `fair_cycle`, as single states, or `None` if `fair` is not
+/// reachable.
+pub fn fair_lasso<G: FairGraph>(
+    graph: &G,
+    init: &G::Set,
+    reach: &G::Set,
+    fair: &G::Set,
+) -> Option<Lasso<G::Set>> {
+    let mut stem = shortest_path(graph, init, fair, reach, 0)?;
+    let justice = graph.justice();
+    loop {
+        let start = stem.pop().unwrap();
+        let mut cycle = vec![start.clone()];
+        for justice in justice.iter() {
+            if cycle
+                .iter()
+                .any(|state| !graph.is_empty(&graph.and(state, justice)))
+            {
+                continue;
+            }
+            let target = graph.and(justice, fair);
+            let path = shortest_path(graph, cycle.last().unwrap(), &target, fair, 1)?;
+            cycle.extend(path.into_iter().skip(1));
+        }
+        // the loop may have left the SCC of `start`, then it starts again from its end
+        match shortest_path(graph, cycle.last().unwrap(), &start, fair, 1) {
+            Some(mut path) => {
+                path.pop();
+                cycle.extend(path.into_iter().skip(1));
+                return Some((stem, cycle));
+            }
+            None => stem.extend(cycle),
+        }
+    }
+}
+
+impl<BM: BddManager> FairGraph for FsmBdd<BM>
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    type Set = BM::Bdd;
+
+    fn empty(&self) -> BM::Bdd {
+        self.manager.constant(false)
+    }
+
+    fn and(&self, a: &BM::Bdd, b: &BM::Bdd) -> BM::Bdd {
+        a & b
+    }
+
+    fn or(&self, a: &BM::Bdd, b: &BM::Bdd) -> BM::Bdd {
+        a | b
+    }
+
+    fn diff(&self, a: &BM::Bdd, b: &BM::Bdd) -> BM::Bdd {
+        a & !b
+    }
+
+    fn is_empty(&self, a: &BM::Bdd) -> bool {
+        a.is_constant(false)
+    }
+
+    fn pre_image(&self, a: &BM::Bdd) -> BM::Bdd {
+        FsmBdd::pre_image(self, a)
+    }
+
+    fn post_image(&self, a: &BM::Bdd) -> BM::Bdd {
+        FsmBdd::post_image(self, a)
+    }
+
+    fn justice(&self) -> Vec<BM::Bdd> {
+        self.justice.clone()
+    }
+
+    fn pick(&self, a: &BM::Bdd) -> BM::Bdd {
+        pick_state(&self.manager, a)
+    }
+
+    fn reachable(&self, from: &BM::Bdd, forward: bool, constrain: &BM::Bdd) -> BM::Bdd {
+        self.reachable_with_constrain(from, forward, true, constrain)
+    }
+}
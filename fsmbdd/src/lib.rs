@@ -1,4 +1,6 @@
+mod fair;
 mod trans;
+pub use fair::*;
 pub use trans::*;
 
 use bdds::BddManager;
@@ -70,6 +72,20 @@ where
         contain_from: bool,
         constrain: &BM::Bdd,
     ) -> BM::Bdd {
+        if self.trans.is_saturation() {
+            let constrain = constrain & &self.invariants;
+            let mut from = state & &constrain;
+            if !contain_from {
+                from = if forward {
+                    self.post_image(&from)
+                } else {
+                    self.pre_image(&from)
+                } & &constrain;
+            }
+            return self
+                .trans
+                .saturate(&from, forward, &self.invariants, &constrain);
+        }
         let mut frontier = state.clone() & constrain & &self.invariants;
         let mut reach = if contain_from {
             frontier.clone()
@@ -94,6 +110,38 @@ where
         }
     }
 
+    /// States reachable from `state` with steps only leaving states in `guard`, every state
+    /// on the way is restricted to `constrain`.
+    pub fn reachable_with_guard(
+        &self,
+        state: &BM::Bdd,
+        forward: bool,
+        guard: &BM::Bdd,
+        constrain: &BM::Bdd,
+    ) -> BM::Bdd {
+        let constrain = constrain & &self.invariants;
+        let mut reach = state & &constrain;
+        if self.trans.is_saturation() {
+            let guard = guard & &self.invariants;
+            return self.trans.saturate(&reach, forward, &guard, &constrain);
+        }
+        let mut frontier = reach.clone();
+        loop {
+            let from = frontier & guard;
+            let new_frontier = if forward {
+                self.post_image(&from)
+            } else {
+                self.pre_image(&from)
+            } & &constrain;
+            let new_frontier = new_frontier & !&reach;
+            if new_frontier == self.manager.constant(false) {
+                break reach;
+            }
+            reach |= &new_frontier;
+            frontier = new_frontier;
+        }
+    }
+
     pub fn reachable(&self, state: &BM::Bdd, forward: bool, contain_from: bool) -> BM::Bdd {
         self.reachable_with_constrain(state, forward, contain_from, &self.manager.constant(true))
     }
@@ -121,6 +169,43 @@ where
         }
     }
 
+    pub fn fair_cycle_with_algorithm(
+        &self,
+        constrain: &BM::Bdd,
+        algorithm: FairCycleAlgorithm,
+    ) -> BM::Bdd {
+        fair::fair_cycle(self, constrain, algorithm)
+    }
+
+    /// k-liveness on the states in `forward`. Round `k` keeps the states reached after visiting
+    /// every justice set `k` times in order, each round is a forward reachability inside
+    /// `forward`. Returns `Some(k)` once round `k` is empty, i.e. no path visits the justice
+    /// sets more than `k - 1` times, and `None` if the rounds reach a nonempty fixpoint, which
+    /// contains a fair cycle.
+    pub fn k_liveness(&self, forward: &BM::Bdd) -> Option<usize> {
+        let mut justice = self.justice.clone();
+        if justice.is_empty() {
+            justice.push(self.manager.constant(true));
+        }
+        let mut res = forward.clone();
+        let mut k = 0;
+        loop {
+            let mut new = res.clone();
+            for fair in justice.iter() {
+                let fair = fair & &new;
+                new = self.reachable_with_constrain(&fair, true, false, forward);
+            }
+            k += 1;
+            if new == self.manager.constant(false) {
+                break Some(k);
+            }
+            if new == res {
+                break None;
+            }
+            res = new;
+        }
+    }
+
     pub fn fair_cycle(&self) -> BM::Bdd {
         self.fair_cycle_with_constrain(&self.manager.constant(true))
     }
@@ -145,3 +230,330 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        fair_lasso, FairCycleAlgorithm, FsmBdd, Trans, TransBddMethod, DEFAULT_CLUSTER_THRESHOLD,
+    };
+    use peabody::{Bdd, Peabody};
+    use std::collections::HashMap;
+
+    fn iff(a: &Bdd, b: &Bdd) -> Bdd {
+        !(a ^ b)
+    }
+
+    /// Two bit counter `x1 x0` counting from `00`, `11` is a sink if `stop` is set.
+    fn counter(method: TransBddMethod, stop: bool) -> FsmBdd<Peabody> {
+        let manager = Peabody::new();
+        let x0 = manager.ith_var(0);
+        let x0p = manager.ith_var(1);
+        let x1 = manager.ith_var(2);
+        let x1p = manager.ith_var(3);
+        let sink = if stop {
+            &x0 & &x1
+        } else {
+            manager.constant(false)
+        };
+        let trans = vec![
+            iff(&x0p, &(!&x0 | &sink)),
+            iff(&x1p, &((&x1 ^ &x0) | &sink)),
+        ];
+        FsmBdd {
+            symbols: HashMap::from([("x0".to_string(), 0), ("x1".to_string(), 2)]),
+            init: !&x0 & !&x1,
+            invariants: manager.constant(true),
+            trans: Trans::new(&manager, trans, method),
+            justice: vec![&x1 & !&x0],
+            manager,
+        }
+    }
+
+    #[test]
+    fn test_image() {
+        for method in [TransBddMethod::Partition, TransBddMethod::Monolithic] {
+            let fsmbdd = counter(method, false);
+            let x0 = fsmbdd.manager.ith_var(0);
+            let x1 = fsmbdd.manager.ith_var(2);
+            assert_eq!(fsmbdd.post_image(&fsmbdd.init), &x0 & !&x1);
+            assert_eq!(fsmbdd.pre_image(&fsmbdd.init), &x0 & &x1);
+            assert_eq!(fsmbdd.reachable_from_init(), fsmbdd.manager.constant(true));
+        }
+    }
+
+    /// Two processes chosen by the input `sel`, each toggles its own bit, `b` only while `a`.
+    fn interleaving(manager: &Peabody, method: TransBddMethod) -> FsmBdd<Peabody> {
+        let sel = manager.ith_var(0);
+        let _ = manager.ith_var(1);
+        let a = manager.ith_var(2);
+        let ap = manager.ith_var(3);
+        let b = manager.ith_var(4);
+        let bp = manager.ith_var(5);
+        let trans = vec![
+            iff(&ap, &sel.if_then_else(&a, &!&a)),
+            iff(&bp, &(&sel & &a).if_then_else(&!&b, &b)),
+        ];
+        FsmBdd {
+            symbols: HashMap::from([
+                ("sel".to_string(), 0),
+                ("a".to_string(), 2),
+                ("b".to_string(), 4),
+            ]),
+            init: !&a & !&b & !&sel,
+            invariants: manager.constant(true),
+            trans: Trans::new(manager, trans, method),
+            justice: vec![&b & !&a],
+            manager: manager.clone(),
+        }
+    }
+
+    #[test]
+    fn test_saturation() {
+        let manager = Peabody::new();
+        let bfs = interleaving(&manager, TransBddMethod::Partition);
+        let sat = interleaving(&manager, TransBddMethod::Saturation);
+        assert!(sat.trans.is_saturation());
+        let reach = sat.reachable_from_init();
+        assert_eq!(reach, bfs.reachable_from_init());
+        assert_eq!(reach, sat.manager.constant(true));
+        let target = sat.manager.ith_var(4) & !sat.manager.ith_var(2);
+        assert_eq!(
+            sat.reachable(&target, false, false),
+            bfs.reachable(&target, false, false)
+        );
+        assert_eq!(sat.fair_cycle(), bfs.fair_cycle());
+        let guard = !sat.manager.ith_var(2);
+        let t = sat.manager.constant(true);
+        assert_eq!(
+            sat.reachable_with_guard(&sat.init, true, &guard, &t),
+            bfs.reachable_with_guard(&sat.init, true, &guard, &t)
+        );
+    }
+
+    /// `x` toggles while the state variable `sel` is set and keeps its value otherwise, the
+    /// event of `!sel` only has frames but still changes `sel`.
+    fn toggle(manager: &Peabody, method: TransBddMethod) -> FsmBdd<Peabody> {
+        let sel = manager.ith_var(0);
+        let _ = manager.ith_var(1);
+        let x = manager.ith_var(2);
+        let xp = manager.ith_var(3);
+        let trans = vec![iff(&xp, &sel.if_then_else(&!&x, &x))];
+        FsmBdd {
+            symbols: HashMap::from([("sel".to_string(), 0), ("x".to_string(), 2)]),
+            init: !&x & !&sel,
+            invariants: manager.constant(true),
+            trans: Trans::new(manager, trans, method),
+            justice: vec![],
+            manager: manager.clone(),
+        }
+    }
+
+    #[test]
+    fn test_saturation_frame_events() {
+        let manager = Peabody::new();
+        let bfs = toggle(&manager, TransBddMethod::Partition);
+        let sat = toggle(&manager, TransBddMethod::Saturation);
+        assert!(sat.trans.is_saturation());
+        let reach = sat.reachable_from_init();
+        assert_eq!(reach, bfs.reachable_from_init());
+        // `G !x` fails
+        assert!(!(&reach & manager.ith_var(2)).is_constant(false));
+        let guard = manager.constant(true);
+        assert_eq!(
+            sat.reachable_with_guard(&sat.init, true, &guard, &guard),
+            reach
+        );
+    }
+
+    #[test]
+    fn test_disjunctive() {
+        let manager = Peabody::new();
+        let con = interleaving(&manager, TransBddMethod::Partition);
+        let dis = interleaving(&manager, TransBddMethod::Disjunctive);
+        let a = manager.ith_var(2);
+        let b = manager.ith_var(4);
+        let state = !&a & &b;
+        assert_eq!(dis.post_image(&state), con.post_image(&state));
+        assert_eq!(dis.pre_image(&state), con.pre_image(&state));
+        assert_eq!(dis.fair_cycle(), con.fair_cycle());
+        let reach = con.reachable_from_init();
+        assert_eq!(dis.product(&con).reachable_from_init(), reach);
+        assert_eq!(dis.product(&dis).reachable_from_init(), reach);
+    }
+
+    #[test]
+    fn test_many_partitions() {
+        // a rotating shift register with more conjuncts than a single cluster can hold
+        const N: usize = 120;
+        let manager = Peabody::new();
+        let x: Vec<Bdd> = (0..2 * N).map(|i| manager.ith_var(i)).collect();
+        let trans: Vec<Bdd> = (0..N)
+            .map(|i| iff(&x[2 * i + 1], &x[2 * ((i + N - 1) % N)]))
+            .collect();
+        let init = (1..N).fold(x[0].clone(), |init, i| init & !&x[2 * i]);
+        for threshold in [1, DEFAULT_CLUSTER_THRESHOLD] {
+            let partition = Trans::new_with_threshold(
+                &manager,
+                trans.clone(),
+                TransBddMethod::Partition,
+                threshold,
+            );
+            let expect = (0..N).fold(
+                x[2].clone(),
+                |next, i| {
+                    if i == 1 {
+                        next
+                    } else {
+                        next & !&x[2 * i]
+                    }
+                },
+            );
+            assert_eq!(partition.post_image(&init), expect);
+            assert_eq!(partition.pre_image(&expect), init);
+            // every cluster is conjoined and every variable quantified exactly once
+            for (forward, parity) in [(true, 0), (false, 1)] {
+                let schedule = partition.schedule(forward);
+                let mut clusters: Vec<usize> = schedule.iter().map(|(i, _, _)| *i).collect();
+                clusters.sort();
+                assert_eq!(
+                    clusters,
+                    (0..partition.partitions().len()).collect::<Vec<_>>()
+                );
+                let mut vars: Vec<usize> =
+                    schedule.into_iter().flat_map(|(_, _, vars)| vars).collect();
+                vars.sort();
+                assert_eq!(vars, (0..N).map(|i| 2 * i + parity).collect::<Vec<_>>());
+            }
+        }
+    }
+
+    #[cfg(feature = "lace")]
+    #[test]
+    fn test_split_image() {
+        // the shift register of `test_many_partitions` on a state set large enough to be split
+        const N: usize = 120;
+        const K: usize = 10;
+        sylvan::Sylvan::new();
+        let manager = Peabody::new();
+        let x: Vec<Bdd> = (0..2 * N).map(|i| manager.ith_var(i)).collect();
+        let trans: Vec<Bdd> = (0..N)
+            .map(|i| iff(&x[2 * i + 1], &x[2 * ((i + N - 1) % N)]))
+            .collect();
+        let state = (0..K).fold(manager.constant(true), |state, i| {
+            state & iff(&x[2 * i], &x[2 * (i + K)])
+        });
+        assert!(state.size() >= 1000);
+        for threshold in [1, DEFAULT_CLUSTER_THRESHOLD] {
+            let partition = Trans::new_with_threshold(
+                &manager,
+                trans.clone(),
+                TransBddMethod::Partition,
+                threshold,
+            );
+            let mut parallel = partition.clone();
+            parallel.set_parallel(true);
+            let (post, pre) =
+                sylvan::lace_run(|_| (parallel.post_image(&state), parallel.pre_image(&state)));
+            assert_eq!(post, partition.post_image(&state));
+            assert_eq!(pre, partition.pre_image(&state));
+        }
+    }
+
+    #[test]
+    fn test_fair_cycle() {
+        let fsmbdd = counter(TransBddMethod::Partition, false);
+        assert_eq!(fsmbdd.fair_cycle(), fsmbdd.manager.constant(true));
+        let fsmbdd = counter(TransBddMethod::Partition, true);
+        assert_eq!(fsmbdd.fair_cycle(), fsmbdd.manager.constant(false));
+    }
+
+    #[test]
+    fn test_fair_cycle_algorithms() {
+        let manager = Peabody::new();
+        let models = [
+            counter(TransBddMethod::Partition, false),
+            counter(TransBddMethod::Partition, true),
+            interleaving(&manager, TransBddMethod::Partition),
+        ];
+        for fsmbdd in models {
+            let reach = fsmbdd.reachable_from_init();
+            let expect = fsmbdd.fair_cycle_with_constrain(&reach);
+            for algorithm in [
+                FairCycleAlgorithm::EmersonLei,
+                FairCycleAlgorithm::EmersonLeiChaining,
+                FairCycleAlgorithm::Owcty,
+                FairCycleAlgorithm::Lockstep,
+                FairCycleAlgorithm::XieBeerel,
+            ] {
+                let fair = fsmbdd.fair_cycle_with_algorithm(&reach, algorithm);
+                assert_eq!(fair.is_constant(false), expect.is_constant(false));
+                assert_eq!(&fair & !&expect, fsmbdd.manager.constant(false));
+            }
+        }
+    }
+
+    #[test]
+    fn test_fair_lasso() {
+        let manager = Peabody::new();
+        let models = [
+            counter(TransBddMethod::Partition, false),
+            counter(TransBddMethod::Partition, true),
+            interleaving(&manager, TransBddMethod::Partition),
+        ];
+        for fsmbdd in models {
+            let reach = fsmbdd.reachable_from_init();
+            let fair = fsmbdd.fair_cycle_with_constrain(&reach);
+            let Some((stem, cycle)) = fair_lasso(&fsmbdd, &fsmbdd.init, &reach, &fair) else {
+                assert!(fair.is_constant(false));
+                continue;
+            };
+            let first = stem.first().unwrap_or(&cycle[0]);
+            assert!(!(first & &fsmbdd.init).is_constant(false));
+            let path: Vec<&Bdd> = stem.iter().chain(cycle.iter()).chain([&cycle[0]]).collect();
+            for step in path.windows(2) {
+                assert!(!(fsmbdd.post_image(step[0]) & step[1]).is_constant(false));
+            }
+            for justice in fsmbdd.justice.iter() {
+                assert!(cycle
+                    .iter()
+                    .any(|state| !(state & justice).is_constant(false)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_k_liveness() {
+        let manager = Peabody::new();
+        let models = [
+            counter(TransBddMethod::Partition, false),
+            counter(TransBddMethod::Partition, true),
+            interleaving(&manager, TransBddMethod::Partition),
+        ];
+        for fsmbdd in models {
+            let reach = fsmbdd.reachable_from_init();
+            let fair_cycle = fsmbdd.fair_cycle_with_constrain(&reach);
+            assert_eq!(
+                fsmbdd.k_liveness(&reach).is_none(),
+                !fair_cycle.is_constant(false)
+            );
+        }
+        // the stopping counter passes `10` once before it sticks in `11`
+        let fsmbdd = counter(TransBddMethod::Partition, true);
+        assert_eq!(fsmbdd.k_liveness(&fsmbdd.reachable_from_init()), Some(2));
+    }
+
+    #[test]
+    fn test_clone_with_new_manager() {
+        let fsmbdd = counter(TransBddMethod::Partition, true);
+        let clone = fsmbdd.clone_with_new_manager();
+        assert!(clone.manager != fsmbdd.manager);
+        let reach = clone.reachable_from_init();
+        assert_eq!(
+            fsmbdd.manager.translocate(&reach),
+            fsmbdd.reachable_from_init()
+        );
+        let exported = bdds::Bdd::export(&reach);
+        let imported = bdds::BddManager::import(&fsmbdd.manager, &exported);
+        assert_eq!(imported, fsmbdd.reachable_from_init());
+    }
+}
@@ -1,14 +1,94 @@
 use bdds::{Bdd, BddManager};
 use ordered_float::NotNan;
 use std::{
-    collections::{BinaryHeap, HashSet},
+    collections::{BinaryHeap, HashMap, HashSet},
     ops::{BitAnd, BitOr, BitXor, Not},
+    sync::Arc,
 };
 
 #[derive(Clone, Debug)]
 pub enum TransBddMethod {
     Partition,
     Monolithic,
+    Saturation,
+    Disjunctive,
+}
+
+/// Default node limit of a cluster built by conjunctive partitioning.
+pub const DEFAULT_CLUSTER_THRESHOLD: usize = 1000;
+
+/// The conjunctive clusters with their image schedules, built once and shared by the clones
+/// of a `Trans` and by its parallel image tasks.
+#[derive(Debug)]
+struct Clusters<BM: BddManager>
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    manager: BM,
+    trans: Vec<BM::Bdd>,
+    // clusters in image order, each with the variables quantified right after it
+    pre_schedule: Vec<(usize, Vec<usize>)>,
+    post_schedule: Vec<(usize, Vec<usize>)>,
+}
+
+impl<BM: BddManager> Clusters<BM>
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    fn image(&self, state: &BM::Bdd, forward: bool) -> BM::Bdd {
+        if forward {
+            if self.trans.len() == 1 {
+                return state.post_image(&self.trans[0]);
+            }
+            let mut res = state.clone();
+            for (i, vars) in self.post_schedule.iter() {
+                res = res.and_abstract(&self.trans[*i], vars.iter().copied());
+            }
+            res.previous_state()
+        } else {
+            if self.trans.len() == 1 {
+                return state.pre_image(&self.trans[0]);
+            }
+            let mut res = state.next_state();
+            for (i, vars) in self.pre_schedule.iter() {
+                res = res.and_abstract(&self.trans[*i], vars.iter().copied());
+            }
+            res
+        }
+    }
+
+    /// The clusters are conjoined one after another into the image with the variables
+    /// quantified as early as the schedule allows, imaging clusters in parallel would mean
+    /// conjoining partial products that keep all their variables. Instead the state set is
+    /// split on its top variables, the parts go through the whole schedule as parallel Lace
+    /// tasks that share the clusters and their images are joined.
+    #[cfg(feature = "lace")]
+    fn split_image(self: &Arc<Self>, state: &BM::Bdd, forward: bool, depth: usize) -> BM::Bdd {
+        const MIN_SPLIT_SIZE: usize = 1000;
+        if depth == 0 || state.size() < MIN_SPLIT_SIZE {
+            return self.image(state, forward);
+        }
+        let var = self.manager.ith_var(state.support_index()[0]);
+        let mut context = sylvan::LaceWorkerContext::get();
+        let high = state & &var;
+        let clusters = self.clone();
+        context.lace_spawn(move |_| clusters.split_image(&high, forward, depth - 1));
+        let low = self.split_image(&(state & !var), forward, depth - 1);
+        let high: BM::Bdd = context.lace_sync();
+        low | high
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -23,9 +103,12 @@ where
         + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
 {
     manager: BM,
-    pub(crate) trans: Vec<BM::Bdd>,
-    pre_eliminate: Vec<Vec<usize>>,
-    post_eliminate: Vec<Vec<usize>>,
+    clusters: Arc<Clusters<BM>>,
+    // disjunctive events for saturation, grouped by level, bottom level first
+    events: Vec<Vec<Trans<BM>>>,
+    // the images are the union of the images of the disjuncts, if any
+    disjuncts: Vec<Trans<BM>>,
+    parallel: bool,
 }
 
 impl<BM: BddManager> Trans<BM>
@@ -38,32 +121,92 @@ where
         + BitXor<BM::Bdd, Output = BM::Bdd>
         + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
 {
-    fn build_schedule(trans: &[BM::Bdd], vars: Vec<usize>) -> Vec<Vec<usize>> {
-        let mut res = Vec::new();
+    /// Orders the clusters in the spirit of IWLS95. The next cluster is the one that allows
+    /// the largest share of its variables to be quantified right away, while bringing the fewest
+    /// new variables into the intermediate product.
+    fn order_clusters(supports: &[HashSet<usize>], vars: &HashSet<usize>) -> Vec<usize> {
+        let mut count: HashMap<usize, usize> = HashMap::new();
+        for support in supports.iter() {
+            for v in support.intersection(vars) {
+                *count.entry(*v).or_default() += 1;
+            }
+        }
+        let mut remain: Vec<usize> = (0..supports.len()).collect();
+        let mut live: HashSet<usize> = HashSet::new();
+        let mut order = Vec::new();
+        while !remain.is_empty() {
+            let score = |i: usize| {
+                let quantify: Vec<usize> = supports[i].intersection(vars).copied().collect();
+                let early = quantify.iter().filter(|v| count[*v] == 1).count();
+                let r1 = if quantify.is_empty() {
+                    0.0
+                } else {
+                    early as f64 / quantify.len() as f64
+                };
+                let product = live.union(&supports[i]).count().max(1);
+                let r2 = quantify.len() as f64 / product as f64;
+                NotNan::new(r1 + r2).unwrap()
+            };
+            let (pos, _) = remain
+                .iter()
+                .enumerate()
+                .max_by_key(|(_, i)| score(**i))
+                .unwrap();
+            let next = remain.remove(pos);
+            for v in supports[next].intersection(vars) {
+                *count.get_mut(v).unwrap() -= 1;
+            }
+            live.extend(supports[next].iter().copied());
+            live.retain(|v| count.get(v) != Some(&0));
+            order.push(next);
+        }
+        order
+    }
+
+    fn build_schedule(supports: &[HashSet<usize>], vars: Vec<usize>) -> Vec<(usize, Vec<usize>)> {
         let mut vars: HashSet<usize> = HashSet::from_iter(vars);
-        for tran in trans.iter().rev() {
-            res.push(Vec::from_iter(vars.iter().copied()));
-            let support = tran.support_index();
-            for v in support.iter() {
+        let order = Self::order_clusters(supports, &vars);
+        let mut res = Vec::new();
+        for i in order.into_iter().rev() {
+            res.push((i, Vec::from_iter(vars.iter().copied())));
+            for v in supports[i].iter() {
                 vars.remove(v);
             }
         }
         res.reverse();
+        let mut quantified = HashSet::new();
+        for (_, vars) in res.iter_mut() {
+            vars.retain(|v| quantified.insert(*v));
+            vars.sort();
+        }
         res
     }
 
-    fn build(manager: &BM, trans: Vec<BM::Bdd>) -> Self {
-        println!("build num trans: {}", trans.len());
-        let pre_eliminate = Self::build_schedule(&trans, manager.next_state_vars());
-        let post_eliminate = Self::build_schedule(&trans, manager.state_vars());
-        Self {
+    fn new_with_schedule(manager: &BM, trans: Vec<BM::Bdd>) -> Self {
+        let supports: Vec<HashSet<usize>> = trans
+            .iter()
+            .map(|tran| HashSet::from_iter(tran.support_index()))
+            .collect();
+        let clusters = Clusters {
             manager: manager.clone(),
-            pre_eliminate,
+            pre_schedule: Self::build_schedule(&supports, manager.next_state_vars()),
+            post_schedule: Self::build_schedule(&supports, manager.state_vars()),
             trans,
-            post_eliminate,
+        };
+        Self {
+            manager: manager.clone(),
+            clusters: Arc::new(clusters),
+            events: Vec::new(),
+            disjuncts: Vec::new(),
+            parallel: false,
         }
     }
 
+    fn build(manager: &BM, trans: Vec<BM::Bdd>) -> Self {
+        println!("build num trans: {}", trans.len());
+        Self::new_with_schedule(manager, trans)
+    }
+
     fn monolithic_new(manager: &BM, trans: Vec<BM::Bdd>) -> Self {
         let mut res = manager.constant(true);
         for (i, tran) in trans.iter().enumerate() {
@@ -73,26 +216,29 @@ where
         Self::build(manager, vec![res])
     }
 
-    fn compute_affinity(a: &BM::Bdd, b: &BM::Bdd) -> f64 {
-        let a: HashSet<usize> = HashSet::from_iter(a.support_index());
-        let b = HashSet::from_iter(b.support_index());
-        let i = a.intersection(&b).count();
-        let u = a.union(&b).count();
+    fn compute_affinity(a: &HashSet<usize>, b: &HashSet<usize>) -> f64 {
+        let i = a.intersection(b).count();
+        let u = a.union(b).count();
+        if u == 0 {
+            return 1.0;
+        }
         i as f64 / u as f64
     }
 
-    fn partition_new(manager: &BM, mut trans: Vec<BM::Bdd>) -> Self {
-        assert!(trans.len() <= 100);
-        const THRESHOLD: usize = 1000;
+    fn partition_new(manager: &BM, mut trans: Vec<BM::Bdd>, threshold: usize) -> Self {
+        let mut supports: Vec<HashSet<usize>> = trans
+            .iter()
+            .map(|tran| HashSet::from_iter(tran.support_index()))
+            .collect();
         let mut trans_exist = HashSet::new();
         let mut res = Vec::new();
         let mut affinity_heap = BinaryHeap::new();
         for i in 0..trans.len() {
-            if trans[i].size() > THRESHOLD {
+            if trans[i].size() > threshold {
                 res.push(trans[i].clone())
             } else {
                 for exist in trans_exist.iter() {
-                    let affinity = Self::compute_affinity(&trans[i], &trans[*exist]);
+                    let affinity = Self::compute_affinity(&supports[i], &supports[*exist]);
                     let affinity = NotNan::new(affinity).unwrap();
                     affinity_heap.push((affinity, *exist, i));
                 }
@@ -103,13 +249,15 @@ where
             if trans_exist.contains(&x) && trans_exist.contains(&y) {
                 let xy = &trans[x] & &trans[y];
                 assert!(trans_exist.remove(&x) && trans_exist.remove(&y));
-                if xy.size() > THRESHOLD {
+                if xy.size() > threshold {
                     res.push(xy);
                 } else {
                     let xy_index = trans.len();
                     trans.push(xy);
+                    supports.push(supports[x].union(&supports[y]).copied().collect());
                     for exist in trans_exist.iter() {
-                        let affinity = Self::compute_affinity(&trans[xy_index], &trans[*exist]);
+                        let affinity =
+                            Self::compute_affinity(&supports[xy_index], &supports[*exist]);
                         let affinity = NotNan::new(affinity).unwrap();
                         affinity_heap.push((affinity, *exist, xy_index));
                     }
@@ -126,7 +274,122 @@ where
         Self::build(manager, res)
     }
 
+    fn event_new(manager: &BM, mut trans: Vec<BM::Bdd>) -> Self {
+        trans.retain(|tran| !tran.is_constant(true));
+        if trans.is_empty() {
+            trans.push(manager.constant(true));
+        }
+        Self::new_with_schedule(manager, trans)
+    }
+
+    fn is_frame(manager: &BM, tran: &BM::Bdd) -> bool {
+        let support = tran.support_index();
+        support.len() == 2
+            && support[0] % 2 == 0
+            && support[1] == support[0] + 1
+            && *tran == !(manager.ith_var(support[0]) ^ manager.ith_var(support[1]))
+    }
+
+    /// Splits the conjunctive partitions into disjunctive events. The split variables are the
+    /// current state variables whose next state is unconstrained, e.g. the process selectors
+    /// of asynchronous models. A variable is only split on if it makes more partitions frame
+    /// conditions, so ordinary inputs are kept. The cube of split variables is the last partition
+    /// of an event.
+    fn split_events(manager: &BM, trans: &[BM::Bdd]) -> Vec<Vec<BM::Bdd>> {
+        const MAX_EVENTS: usize = 4096;
+        let num_frame = |events: &[(Vec<BM::Bdd>, BM::Bdd)]| {
+            events
+                .iter()
+                .flat_map(|(event, _)| event.iter())
+                .filter(|tran| Self::is_frame(manager, tran))
+                .count()
+        };
+        let mut support = HashSet::new();
+        for tran in trans.iter() {
+            support.extend(tran.support_index());
+        }
+        let mut candidates: Vec<usize> = support
+            .iter()
+            .filter(|v| *v % 2 == 0 && !support.contains(&(*v + 1)))
+            .copied()
+            .collect();
+        candidates.sort();
+        let mut events = vec![(trans.to_vec(), manager.constant(true))];
+        for var in candidates {
+            if events.len() * 2 > MAX_EVENTS {
+                break;
+            }
+            let mut new_events = Vec::new();
+            for (event, cube) in events.iter() {
+                for lit in [!manager.ith_var(var), manager.ith_var(var)] {
+                    let event: Vec<BM::Bdd> = event
+                        .iter()
+                        .map(|tran| tran.and_abstract(&lit, [var]))
+                        .collect();
+                    if event.iter().all(|tran| !tran.is_constant(false)) {
+                        new_events.push((event, cube & &lit));
+                    }
+                }
+            }
+            // frames per event must grow
+            if num_frame(&new_events) * events.len() > num_frame(&events) * new_events.len() {
+                events = new_events;
+            }
+        }
+        events
+            .into_iter()
+            .map(|(mut event, cube)| {
+                event.push(cube);
+                event
+            })
+            .collect()
+    }
+
+    /// Groups the events by level, the level of an event is the first variable it may change.
+    /// An event whose partitions are all frames still changes the variables whose next state
+    /// it leaves free, e.g. the split variables, it is only dropped if there are none.
+    fn build_events(manager: &BM, trans: &[BM::Bdd]) -> Vec<Vec<Self>> {
+        let mut levels: Vec<(usize, Vec<Self>)> = Vec::new();
+        for event in Self::split_events(manager, trans) {
+            let level = event
+                .iter()
+                .filter(|tran| !Self::is_frame(manager, tran))
+                .flat_map(|tran| tran.support_index())
+                .filter(|v| v % 2 == 1)
+                .min();
+            let level = level.or_else(|| {
+                let support: HashSet<usize> =
+                    event.iter().flat_map(|tran| tran.support_index()).collect();
+                manager
+                    .next_state_vars()
+                    .into_iter()
+                    .filter(|v| !support.contains(v))
+                    .min()
+            });
+            let Some(level) = level else {
+                continue;
+            };
+            let event = Self::event_new(manager, event);
+            match levels.iter_mut().find(|(l, _)| *l == level) {
+                Some((_, level)) => level.push(event),
+                None => levels.push((level, vec![event])),
+            }
+        }
+        levels.sort_by(|(a, _), (b, _)| b.cmp(a));
+        levels.into_iter().map(|(_, level)| level).collect()
+    }
+
     pub fn new(manager: &BM, trans: Vec<BM::Bdd>, method: TransBddMethod) -> Self {
+        Self::new_with_threshold(manager, trans, method, DEFAULT_CLUSTER_THRESHOLD)
+    }
+
+    /// Like [`Trans::new`], clusters are only merged while they stay within `threshold` nodes.
+    pub fn new_with_threshold(
+        manager: &BM,
+        trans: Vec<BM::Bdd>,
+        method: TransBddMethod,
+        threshold: usize,
+    ) -> Self {
         let trans = {
             let mut res = vec![];
             for tran in trans {
@@ -138,54 +401,230 @@ where
         };
         dbg!(trans.len());
         match method {
-            TransBddMethod::Partition => Self::partition_new(manager, trans),
+            TransBddMethod::Partition => Self::partition_new(manager, trans, threshold),
             TransBddMethod::Monolithic => Self::monolithic_new(manager, trans),
+            TransBddMethod::Saturation => {
+                let events = Self::build_events(manager, &trans);
+                let mut res = Self::partition_new(manager, trans, threshold);
+                res.events = events;
+                res
+            }
+            TransBddMethod::Disjunctive => {
+                let disjuncts: Vec<Self> = Self::split_events(manager, &trans)
+                    .into_iter()
+                    .map(|event| Self::event_new(manager, event))
+                    .collect();
+                let mut res = Self::partition_new(manager, trans, threshold);
+                res.disjuncts = disjuncts;
+                res
+            }
         }
     }
 
-    pub fn pre_image(&self, state: &BM::Bdd) -> BM::Bdd {
-        if self.trans.len() == 1 {
-            state.pre_image(&self.trans[0])
-        } else {
-            let mut res = state.next_state();
-            for i in 0..self.pre_eliminate.len() {
-                res = res.and_abstract(&self.trans[i], self.pre_eliminate[i].iter().copied());
+    /// Computes the images as parallel Lace tasks, the caller must run on a Lace worker and the
+    /// BDD manager must be thread-safe. Disjuncts are imaged concurrently, otherwise the state
+    /// set is split into parts that are imaged concurrently.
+    pub fn set_parallel(&mut self, parallel: bool) {
+        self.parallel = parallel;
+    }
+
+    fn disjunctive_image(&self, state: &BM::Bdd, forward: bool) -> BM::Bdd {
+        #[cfg(feature = "lace")]
+        if self.parallel {
+            let mut context = sylvan::LaceWorkerContext::get();
+            for disjunct in self.disjuncts.iter() {
+                let disjunct = disjunct.clone();
+                let state = state.clone();
+                context.lace_spawn(move |_| {
+                    if forward {
+                        disjunct.post_image(&state)
+                    } else {
+                        disjunct.pre_image(&state)
+                    }
+                });
             }
-            res
+            return context
+                .lace_sync_multi::<BM::Bdd>(self.disjuncts.len())
+                .into_iter()
+                .fold(self.manager.constant(false), |res, image| res | image);
+        }
+        let mut res = self.manager.constant(false);
+        for disjunct in self.disjuncts.iter() {
+            res |= if forward {
+                disjunct.post_image(state)
+            } else {
+                disjunct.pre_image(state)
+            };
         }
+        res
     }
 
-    pub fn post_image(&self, state: &BM::Bdd) -> BM::Bdd {
-        if self.trans.len() == 1 {
-            state.post_image(&self.trans[0])
-        } else {
-            let mut res = state.clone();
-            for i in 0..self.post_eliminate.len() {
-                res = res.and_abstract(&self.trans[i], self.post_eliminate[i].iter().copied());
-            }
-            res.previous_state()
+    fn image(&self, state: &BM::Bdd, forward: bool) -> BM::Bdd {
+        if !self.disjuncts.is_empty() {
+            return self.disjunctive_image(state, forward);
+        }
+        #[cfg(feature = "lace")]
+        if self.parallel {
+            const SPLIT_DEPTH: usize = 3;
+            return self.clusters.split_image(state, forward, SPLIT_DEPTH);
         }
+        self.clusters.image(state, forward)
+    }
+
+    pub fn pre_image(&self, state: &BM::Bdd) -> BM::Bdd {
+        self.image(state, false)
+    }
+
+    pub fn post_image(&self, state: &BM::Bdd) -> BM::Bdd {
+        self.image(state, true)
     }
 
     pub fn product(&self, other: &Self) -> Self {
         assert!(self.manager == other.manager);
-        let trans = if self.trans.len() == 1 && other.trans.len() == 1 {
-            vec![&self.trans[0] & &other.trans[0]]
+        let trans = if self.clusters.trans.len() == 1 && other.clusters.trans.len() == 1 {
+            vec![&self.clusters.trans[0] & &other.clusters.trans[0]]
         } else {
-            let mut trans = self.trans.clone();
-            trans.extend(other.trans.clone());
+            let mut trans = self.clusters.trans.clone();
+            trans.extend(other.clusters.trans.clone());
             trans
         };
-        Self::build(&self.manager, trans)
+        let mut res = Self::build(&self.manager, trans);
+        let (events, sync) = if self.events.is_empty() {
+            (&other.events, &self.clusters.trans)
+        } else {
+            (&self.events, &other.clusters.trans)
+        };
+        res.events = events
+            .iter()
+            .map(|level| {
+                level
+                    .iter()
+                    .map(|event| {
+                        let mut trans = event.clusters.trans.clone();
+                        trans.extend(sync.iter().cloned());
+                        Self::event_new(&self.manager, trans)
+                    })
+                    .collect()
+            })
+            .collect();
+        let sync = |event: &Self, sync: &Self| {
+            let mut trans = event.clusters.trans.clone();
+            trans.extend(sync.clusters.trans.iter().cloned());
+            Self::event_new(&self.manager, trans)
+        };
+        res.disjuncts = match (self.disjuncts.is_empty(), other.disjuncts.is_empty()) {
+            (true, true) => Vec::new(),
+            (false, true) => self.disjuncts.iter().map(|d| sync(d, other)).collect(),
+            (true, false) => other.disjuncts.iter().map(|d| sync(d, self)).collect(),
+            (false, false) => self
+                .disjuncts
+                .iter()
+                .flat_map(|x| other.disjuncts.iter().map(|y| sync(x, y)))
+                .collect(),
+        };
+        res.parallel = self.parallel || other.parallel;
+        res
     }
 
     pub fn clone_with_new_manager(&self, manager: &BM) -> Self {
-        let trans = self.trans.iter().map(|t| manager.translocate(t)).collect();
+        let clusters = Clusters {
+            manager: manager.clone(),
+            trans: self
+                .clusters
+                .trans
+                .iter()
+                .map(|t| manager.translocate(t))
+                .collect(),
+            pre_schedule: self.clusters.pre_schedule.clone(),
+            post_schedule: self.clusters.post_schedule.clone(),
+        };
+        let events = self
+            .events
+            .iter()
+            .map(|level| {
+                level
+                    .iter()
+                    .map(|event| event.clone_with_new_manager(manager))
+                    .collect()
+            })
+            .collect();
         Self {
             manager: manager.clone(),
-            trans,
-            pre_eliminate: self.pre_eliminate.clone(),
-            post_eliminate: self.post_eliminate.clone(),
+            clusters: Arc::new(clusters),
+            events,
+            disjuncts: self
+                .disjuncts
+                .iter()
+                .map(|disjunct| disjunct.clone_with_new_manager(manager))
+                .collect(),
+            parallel: self.parallel,
+        }
+    }
+
+    /// The clusters in the order they are conjoined into a post-image, or a pre-image if not
+    /// `forward`, each as its index, its size and the variables quantified right after it.
+    pub fn schedule(&self, forward: bool) -> Vec<(usize, usize, Vec<usize>)> {
+        let clusters = &self.clusters;
+        let schedule = if forward {
+            &clusters.post_schedule
+        } else {
+            &clusters.pre_schedule
+        };
+        schedule
+            .iter()
+            .map(|(i, vars)| (*i, clusters.trans[*i].size(), vars.clone()))
+            .collect()
+    }
+
+    /// The clusters, their conjunction is the transition relation for every method.
+    pub fn partitions(&self) -> &[BM::Bdd] {
+        &self.clusters.trans
+    }
+
+    pub fn is_saturation(&self) -> bool {
+        !self.events.is_empty()
+    }
+
+    /// The least set containing `from` that is closed under the images of the states in `guard`,
+    /// with the images restricted to `constrain`. Events are fired level by level from the bottom,
+    /// every time a level adds states, the levels below it are saturated again.
+    pub fn saturate(
+        &self,
+        from: &BM::Bdd,
+        forward: bool,
+        guard: &BM::Bdd,
+        constrain: &BM::Bdd,
+    ) -> BM::Bdd {
+        assert!(self.is_saturation());
+        self.saturate_levels(from.clone(), self.events.len(), forward, guard, constrain)
+    }
+
+    fn saturate_levels(
+        &self,
+        mut state: BM::Bdd,
+        num_level: usize,
+        forward: bool,
+        guard: &BM::Bdd,
+        constrain: &BM::Bdd,
+    ) -> BM::Bdd {
+        for level in 0..num_level {
+            loop {
+                let mut new = state.clone();
+                for event in self.events[level].iter() {
+                    let from = &new & guard;
+                    let image = if forward {
+                        event.post_image(&from)
+                    } else {
+                        event.pre_image(&from)
+                    };
+                    new |= image & constrain;
+                }
+                if new == state {
+                    break;
+                }
+                state = self.saturate_levels(new, level, forward, guard, constrain);
+            }
         }
+        state
     }
 }
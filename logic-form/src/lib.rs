@@ -1,6 +1,3 @@
-mod expr;
-pub use expr::*;
-
 use std::{
     cmp::Ordering,
     collections::HashSet,
@@ -0,0 +1,176 @@
+mod node;
+pub use node::*;
+mod table;
+
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex, MutexGuard},
+};
+use table::{Table, FALSE, TRUE};
+
+/// A pure Rust BDD manager without complement edges and garbage collection. It is slower than
+/// Sylvan and CUDD, but needs no native library, so it is used to run and cross-check tests.
+///
+/// Every `Peabody::new()` creates an independent node table, the table is shared by all
+/// clones of the manager and protected by a lock.
+#[derive(Clone)]
+pub struct Peabody {
+    table: Arc<Mutex<Table>>,
+}
+
+impl Peabody {
+    pub fn new() -> Self {
+        Self::new_with_capacity(1024)
+    }
+
+    pub fn new_with_capacity(capacity: usize) -> Self {
+        Self {
+            table: Arc::new(Mutex::new(Table::new(capacity))),
+        }
+    }
+
+    pub(crate) fn table(&self) -> MutexGuard<'_, Table> {
+        self.table.lock().unwrap()
+    }
+
+    pub fn constant(&self, val: bool) -> Bdd {
+        Bdd::new(self, if val { TRUE } else { FALSE })
+    }
+
+    pub fn ith_var(&self, i: usize) -> Bdd {
+        let node = self.table().ith_var(i);
+        Bdd::new(self, node)
+    }
+
+    pub fn num_var(&self) -> usize {
+        self.table().num_var
+    }
+
+    pub fn cube<I: IntoIterator<Item = (usize, bool)>>(&self, vars: I) -> Bdd {
+        let node = self.table().cube(vars.into_iter().collect());
+        Bdd::new(self, node)
+    }
+
+    /// Moves `bdd` (possibly owned by another manager) into this manager. The nodes are read
+    /// out of the source table first, so the two tables are never locked at the same time.
+    pub fn translocate(&self, bdd: &Bdd) -> Bdd {
+        if *self == bdd.manager {
+            return bdd.clone();
+        }
+        let (nodes, root) = bdd.export();
+        let mut table = self.table();
+        let mut map = vec![FALSE, TRUE];
+        for (var, low, high) in nodes {
+            table.num_var = table.num_var.max(var + 1);
+            let node = table.mk(var as u32, map[low], map[high]);
+            map.push(node);
+        }
+        Bdd::new(self, map[root])
+    }
+}
+
+impl Default for Peabody {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Debug for Peabody {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Peabody({:?})", Arc::as_ptr(&self.table))
+    }
+}
+
+impl PartialEq for Peabody {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.table, &other.table)
+    }
+}
+
+impl Eq for Peabody {}
+
+#[cfg(test)]
+mod tests {
+    use crate::Peabody;
+
+    #[test]
+    fn test_basic() {
+        let peabody = Peabody::new();
+        let a = peabody.ith_var(0);
+        let b = peabody.ith_var(1);
+        let c = &a & &b;
+        let d = !(!&a | !&b);
+        assert_eq!(c, d);
+        assert_eq!(&a ^ &b, (&a & !&b) | (!&a & &b));
+        let t = peabody.constant(true);
+        let f = peabody.constant(false);
+        assert_eq!(&t & &c, c);
+        assert_eq!(t, !f);
+        assert_eq!(a.if_then_else(&b, &t), !&a | &b);
+    }
+
+    #[test]
+    fn test_image() {
+        let peabody = Peabody::new();
+        let a = peabody.ith_var(0);
+        let ap = peabody.ith_var(1);
+        let b = peabody.ith_var(2);
+        let bp = peabody.ith_var(3);
+        let s = &a & &b;
+        let t = &a & &b & !&ap & !&bp;
+        assert_eq!(s.post_image(&t), !&a & !&b);
+        let s = !&a & !&b;
+        assert_eq!(s.pre_image(&t), &a & &b);
+        let s = !&a & &b;
+        assert_eq!(s.pre_image(&t), peabody.constant(false));
+        let t = &ap ^ &a;
+        assert_eq!(a.post_image(&t), !&a);
+        assert_eq!(a.pre_image(&t), !&a);
+    }
+
+    #[test]
+    fn test_and_abstract() {
+        let peabody = Peabody::new();
+        let a = peabody.ith_var(0);
+        let b = peabody.ith_var(2);
+        let c = peabody.ith_var(4);
+        let f = (&a & &b) | (!&a & &c);
+        assert_eq!(f.and_abstract(&peabody.constant(true), [0]), &b | &c);
+        assert_eq!(f.and_abstract(&!&c, [0, 2]), !&c);
+        assert_eq!(f.and_abstract(&!&b, [4]), !&a & !&b);
+    }
+
+    #[test]
+    fn test_support() {
+        let peabody = Peabody::new();
+        let a = peabody.ith_var(0);
+        let _ = peabody.ith_var(1);
+        let b = peabody.ith_var(2);
+        let s = &a & !&b;
+        assert_eq!(s.support(), &a & &b);
+        assert_eq!(s.support_index(), vec![0, 2]);
+        assert_eq!(peabody.cube([(0, true), (2, false)]), s);
+        assert_eq!(peabody.num_var(), 3);
+    }
+
+    #[test]
+    fn test_translocate() {
+        let x = Peabody::new();
+        let y = Peabody::new();
+        let _ = y.ith_var(5);
+        let f = (x.ith_var(0) & !x.ith_var(3)) | x.ith_var(4);
+        let g = y.translocate(&f);
+        assert_eq!(g, (y.ith_var(0) & !y.ith_var(3)) | y.ith_var(4));
+        assert_eq!(x.translocate(&g), f);
+    }
+
+    #[test]
+    fn test_export() {
+        let peabody = Peabody::new();
+        let a = peabody.ith_var(0);
+        let b = peabody.ith_var(2);
+        assert_eq!(peabody.constant(false).export(), (vec![], 0));
+        assert_eq!(peabody.constant(true).export(), (vec![], 1));
+        assert_eq!((&a & !&b).export(), (vec![(2, 1, 0), (0, 0, 2)], 3));
+    }
+}
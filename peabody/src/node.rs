@@ -0,0 +1,213 @@
+use crate::{
+    table::{Table, FALSE, TRUE},
+    Peabody,
+};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not},
+};
+
+#[derive(Clone)]
+pub struct Bdd {
+    pub(crate) manager: Peabody,
+    pub(crate) node: u32,
+}
+
+impl Bdd {
+    pub(crate) fn new(manager: &Peabody, node: u32) -> Self {
+        Self {
+            manager: manager.clone(),
+            node,
+        }
+    }
+
+    fn apply(&self, rhs: &Bdd, f: fn(&mut Table, u32, u32) -> u32) -> Self {
+        assert!(self.manager == rhs.manager);
+        let node = f(&mut self.manager.table(), self.node, rhs.node);
+        Bdd::new(&self.manager, node)
+    }
+}
+
+impl AsRef<Bdd> for Bdd {
+    fn as_ref(&self) -> &Bdd {
+        self
+    }
+}
+
+impl AsMut<Bdd> for Bdd {
+    fn as_mut(&mut self) -> &mut Bdd {
+        self
+    }
+}
+
+impl Debug for Bdd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Bdd({})", self.node)
+    }
+}
+
+impl PartialEq for Bdd {
+    fn eq(&self, other: &Self) -> bool {
+        self.manager == other.manager && self.node == other.node
+    }
+}
+
+impl Eq for Bdd {}
+
+impl Not for Bdd {
+    type Output = Bdd;
+
+    fn not(self) -> Self::Output {
+        !&self
+    }
+}
+
+impl Not for &Bdd {
+    type Output = Bdd;
+
+    fn not(self) -> Self::Output {
+        let node = self.manager.table().not(self.node);
+        Bdd::new(&self.manager, node)
+    }
+}
+
+impl<T: AsRef<Bdd>> BitAnd<T> for Bdd {
+    type Output = Bdd;
+
+    fn bitand(self, rhs: T) -> Self::Output {
+        self.apply(rhs.as_ref(), Table::and)
+    }
+}
+
+impl<T: AsRef<Bdd>> BitAnd<T> for &Bdd {
+    type Output = Bdd;
+
+    fn bitand(self, rhs: T) -> Self::Output {
+        self.apply(rhs.as_ref(), Table::and)
+    }
+}
+
+impl<T: AsRef<Bdd>> BitAndAssign<T> for Bdd {
+    fn bitand_assign(&mut self, rhs: T) {
+        *self = &*self & rhs.as_ref();
+    }
+}
+
+impl<T: AsRef<Bdd>> BitOr<T> for Bdd {
+    type Output = Bdd;
+
+    fn bitor(self, rhs: T) -> Self::Output {
+        self.apply(rhs.as_ref(), Table::or)
+    }
+}
+
+impl<T: AsRef<Bdd>> BitOr<T> for &Bdd {
+    type Output = Bdd;
+
+    fn bitor(self, rhs: T) -> Self::Output {
+        self.apply(rhs.as_ref(), Table::or)
+    }
+}
+
+impl<T: AsRef<Bdd>> BitOrAssign<T> for Bdd {
+    fn bitor_assign(&mut self, rhs: T) {
+        *self = &*self | rhs.as_ref();
+    }
+}
+
+impl<T: AsRef<Bdd>> BitXor<T> for Bdd {
+    type Output = Bdd;
+
+    fn bitxor(self, rhs: T) -> Self::Output {
+        self.apply(rhs.as_ref(), Table::xor)
+    }
+}
+
+impl<T: AsRef<Bdd>> BitXor<T> for &Bdd {
+    type Output = Bdd;
+
+    fn bitxor(self, rhs: T) -> Self::Output {
+        self.apply(rhs.as_ref(), Table::xor)
+    }
+}
+
+impl<T: AsRef<Bdd>> BitXorAssign<T> for Bdd {
+    fn bitxor_assign(&mut self, rhs: T) {
+        *self = &*self ^ rhs.as_ref();
+    }
+}
+
+impl Bdd {
+    pub fn is_constant(&self, value: bool) -> bool {
+        self.node == if value { TRUE } else { FALSE }
+    }
+
+    pub fn size(&self) -> usize {
+        self.manager.table().descendants(self.node).len()
+    }
+
+    pub fn if_then_else(&self, _then: &Bdd, _else: &Bdd) -> Self {
+        assert!(self.manager == _then.manager && self.manager == _else.manager);
+        let node = self.manager.table().ite(self.node, _then.node, _else.node);
+        Bdd::new(&self.manager, node)
+    }
+
+    pub fn and_abstract<I: IntoIterator<Item = usize>>(&self, x: &Bdd, cube: I) -> Self {
+        assert!(self.manager == x.manager);
+        let mut table = self.manager.table();
+        let cube = table.cube(cube.into_iter().map(|x| (x, true)).collect());
+        let node = table.and_abstract(self.node, x.node, cube);
+        drop(table);
+        Bdd::new(&self.manager, node)
+    }
+
+    pub fn support(&self) -> Self {
+        self.manager
+            .cube(self.support_index().into_iter().map(|x| (x, true)))
+    }
+
+    pub fn support_index(&self) -> Vec<usize> {
+        self.manager.table().support_index(self.node)
+    }
+
+    fn compose(&self, map: impl Fn(usize) -> usize) -> Self {
+        let node = self.manager.table().compose(self.node, &map);
+        Bdd::new(&self.manager, node)
+    }
+
+    pub fn next_state(&self) -> Self {
+        self.compose(|i| if i % 2 == 0 { i + 1 } else { i })
+    }
+
+    pub fn previous_state(&self) -> Self {
+        self.compose(|i| if i % 2 == 1 { i - 1 } else { i })
+    }
+
+    pub fn post_image(&self, tran: &Bdd) -> Self {
+        let vars = (0..self.manager.num_var()).step_by(2);
+        self.and_abstract(tran, vars).previous_state()
+    }
+
+    pub fn pre_image(&self, tran: &Bdd) -> Self {
+        let vars = (1..self.manager.num_var()).step_by(2);
+        self.next_state().and_abstract(tran, vars)
+    }
+
+    /// Lists the nodes as `(var, low, high)`, children before parents. References `0` and `1`
+    /// are the constants, reference `i + 2` is the i-th node. Returns the nodes and the root.
+    pub fn export(&self) -> (Vec<(usize, usize, usize)>, usize) {
+        let table = self.manager.table();
+        let mut nodes = Vec::new();
+        let mut map = HashMap::from([(FALSE, 0), (TRUE, 1)]);
+        for id in table.descendants(self.node) {
+            if id == FALSE || id == TRUE {
+                continue;
+            }
+            let node = table.node(id);
+            nodes.push((node.var as usize, map[&node.low], map[&node.high]));
+            map.insert(id, nodes.len() + 1);
+        }
+        (nodes, map[&self.node])
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::{HashMap, HashSet};
+
+pub(crate) const FALSE: u32 = 0;
+pub(crate) const TRUE: u32 = 1;
+const TERMINAL: u32 = u32::MAX;
+const CACHE_LIMIT: usize = 1 << 22;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub(crate) struct Node {
+    pub(crate) var: u32,
+    pub(crate) low: u32,
+    pub(crate) high: u32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Op {
+    Ite,
+    AndAbstract,
+}
+
+/// Node table of a manager. Nodes are never freed, they live as long as the manager.
+pub(crate) struct Table {
+    nodes: Vec<Node>,
+    unique: HashMap<Node, u32>,
+    cache: HashMap<(Op, u32, u32, u32), u32>,
+    pub(crate) num_var: usize,
+}
+
+impl Table {
+    pub(crate) fn new(capacity: usize) -> Self {
+        let terminal = |val| Node {
+            var: TERMINAL,
+            low: val,
+            high: val,
+        };
+        let mut nodes = Vec::with_capacity(capacity.max(2));
+        nodes.push(terminal(FALSE));
+        nodes.push(terminal(TRUE));
+        Self {
+            nodes,
+            unique: HashMap::with_capacity(capacity),
+            cache: HashMap::new(),
+            num_var: 0,
+        }
+    }
+
+    #[inline]
+    pub(crate) fn node(&self, node: u32) -> Node {
+        self.nodes[node as usize]
+    }
+
+    #[inline]
+    fn var(&self, node: u32) -> u32 {
+        self.nodes[node as usize].var
+    }
+
+    pub(crate) fn mk(&mut self, var: u32, low: u32, high: u32) -> u32 {
+        if low == high {
+            return low;
+        }
+        let node = Node { var, low, high };
+        if let Some(id) = self.unique.get(&node) {
+            return *id;
+        }
+        let id = self.nodes.len() as u32;
+        self.nodes.push(node);
+        self.unique.insert(node, id);
+        id
+    }
+
+    pub(crate) fn ith_var(&mut self, var: usize) -> u32 {
+        self.num_var = self.num_var.max(var + 1);
+        self.mk(var as u32, FALSE, TRUE)
+    }
+
+    pub(crate) fn cube(&mut self, mut vars: Vec<(usize, bool)>) -> u32 {
+        vars.sort_by_key(|(var, _)| *var);
+        let mut res = TRUE;
+        for (var, pol) in vars.into_iter().rev() {
+            self.num_var = self.num_var.max(var + 1);
+            res = if pol {
+                self.mk(var as u32, FALSE, res)
+            } else {
+                self.mk(var as u32, res, FALSE)
+            };
+        }
+        res
+    }
+
+    fn cache_get(&self, key: &(Op, u32, u32, u32)) -> Option<u32> {
+        self.cache.get(key).copied()
+    }
+
+    fn cache_put(&mut self, key: (Op, u32, u32, u32), res: u32) {
+        if self.cache.len() >= CACHE_LIMIT {
+            self.cache.clear();
+        }
+        self.cache.insert(key, res);
+    }
+
+    #[inline]
+    fn cofactors(&self, f: u32, var: u32) -> (u32, u32) {
+        let node = self.node(f);
+        if node.var == var {
+            (node.low, node.high)
+        } else {
+            (f, f)
+        }
+    }
+
+    pub(crate) fn ite(&mut self, f: u32, g: u32, h: u32) -> u32 {
+        if f == TRUE || g == h {
+            return g;
+        }
+        if f == FALSE {
+            return h;
+        }
+        if g == TRUE && h == FALSE {
+            return f;
+        }
+        let key = (Op::Ite, f, g, h);
+        if let Some(res) = self.cache_get(&key) {
+            return res;
+        }
+        let var = self.var(f).min(self.var(g)).min(self.var(h));
+        let (f0, f1) = self.cofactors(f, var);
+        let (g0, g1) = self.cofactors(g, var);
+        let (h0, h1) = self.cofactors(h, var);
+        let low = self.ite(f0, g0, h0);
+        let high = self.ite(f1, g1, h1);
+        let res = self.mk(var, low, high);
+        self.cache_put(key, res);
+        res
+    }
+
+    pub(crate) fn not(&mut self, f: u32) -> u32 {
+        self.ite(f, FALSE, TRUE)
+    }
+
+    pub(crate) fn and(&mut self, f: u32, g: u32) -> u32 {
+        self.ite(f, g, FALSE)
+    }
+
+    pub(crate) fn or(&mut self, f: u32, g: u32) -> u32 {
+        self.ite(f, TRUE, g)
+    }
+
+    pub(crate) fn xor(&mut self, f: u32, g: u32) -> u32 {
+        let not_g = self.not(g);
+        self.ite(f, not_g, g)
+    }
+
+    /// Computes `exists cube. f & g`, `cube` is a positive cube of the quantified variables.
+    pub(crate) fn and_abstract(&mut self, f: u32, g: u32, mut cube: u32) -> u32 {
+        if f == FALSE || g == FALSE {
+            return FALSE;
+        }
+        if f == TRUE && g == TRUE {
+            return TRUE;
+        }
+        let var = self.var(f).min(self.var(g));
+        while cube != TRUE && self.var(cube) < var {
+            cube = self.node(cube).high;
+        }
+        if cube == TRUE {
+            return self.and(f, g);
+        }
+        let (f, g) = if f <= g { (f, g) } else { (g, f) };
+        let key = (Op::AndAbstract, f, g, cube);
+        if let Some(res) = self.cache_get(&key) {
+            return res;
+        }
+        let (f0, f1) = self.cofactors(f, var);
+        let (g0, g1) = self.cofactors(g, var);
+        let cube_node = self.node(cube);
+        let res = if cube_node.var == var {
+            let low = self.and_abstract(f0, g0, cube_node.high);
+            if low == TRUE {
+                TRUE
+            } else {
+                let high = self.and_abstract(f1, g1, cube_node.high);
+                self.or(low, high)
+            }
+        } else {
+            let low = self.and_abstract(f0, g0, cube);
+            let high = self.and_abstract(f1, g1, cube);
+            self.mk(var, low, high)
+        };
+        self.cache_put(key, res);
+        res
+    }
+
+    /// Substitutes every variable `i` of `f` by the variable `map(i)`.
+    pub(crate) fn compose(&mut self, f: u32, map: &impl Fn(usize) -> usize) -> u32 {
+        let mut cache = HashMap::new();
+        self.compose_rec(f, map, &mut cache)
+    }
+
+    fn compose_rec(
+        &mut self,
+        f: u32,
+        map: &impl Fn(usize) -> usize,
+        cache: &mut HashMap<u32, u32>,
+    ) -> u32 {
+        if f == TRUE || f == FALSE {
+            return f;
+        }
+        if let Some(res) = cache.get(&f) {
+            return *res;
+        }
+        let node = self.node(f);
+        let low = self.compose_rec(node.low, map, cache);
+        let high = self.compose_rec(node.high, map, cache);
+        let var = self.ith_var(map(node.var as usize));
+        let res = self.ite(var, high, low);
+        cache.insert(f, res);
+        res
+    }
+
+    /// Nodes reachable from `f`, children are placed before their parents.
+    pub(crate) fn descendants(&self, f: u32) -> Vec<u32> {
+        let mut res = Vec::new();
+        let mut visit = HashSet::new();
+        let mut stack = vec![(f, false)];
+        while let Some((node, expanded)) = stack.pop() {
+            if expanded {
+                res.push(node);
+                continue;
+            }
+            if !visit.insert(node) {
+                continue;
+            }
+            stack.push((node, true));
+            if node != TRUE && node != FALSE {
+                let node = self.node(node);
+                stack.push((node.high, false));
+                stack.push((node.low, false));
+            }
+        }
+        res
+    }
+
+    pub(crate) fn support_index(&self, f: u32) -> Vec<usize> {
+        let mut res: Vec<usize> = self
+            .descendants(f)
+            .into_iter()
+            .filter(|node| *node != TRUE && *node != FALSE)
+            .map(|node| self.var(node) as usize)
+            .collect();
+        res.sort();
+        res.dedup();
+        res
+    }
+}
@@ -0,0 +1,133 @@
+use crate::{
+    automata::BuchiAutomata,
+    command::{Algorithm, Args},
+    lace::lace_run,
+    property_driven::{create_async_workers, PPSMC},
+};
+use aiger::{bdd::AigBdd, Aig};
+use bdds::{Bdd, BddManager};
+use std::{
+    ops::{BitAnd, BitOr, BitXor, Not},
+    time::{Duration, Instant},
+};
+
+/// Checks every bad-state and every justice property of an AIGER circuit and prints one verdict
+/// per property. A bad state `b` is checked as `G !b`, a justice property as the absence of a
+/// fair cycle visiting each of its constraints and every fairness constraint.
+pub fn check<BM: BddManager>(manager: BM, aig: Aig, args: Args) -> (bool, Duration)
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    assert!(
+        matches!(
+            args.algorithm,
+            Algorithm::PropertyDriven | Algorithm::Traditional | Algorithm::KLiveness
+        ),
+        "aiger circuits are checked by the property-driven, the traditional or the k-liveness engine"
+    );
+    let aigbdd = AigBdd::new(&manager, &aig);
+    assert!(
+        !aigbdd.bads.is_empty() || !aigbdd.justice.is_empty(),
+        "no property in the circuit"
+    );
+    let mut fsmbdd = aigbdd.to_fsmbdd(args.trans_method.into(), args.cluster_threshold);
+    fsmbdd.trans.set_parallel(args.parallel_image);
+    if !args.export.is_empty() {
+        Aig::from_fsmbdd(&fsmbdd).write_file(&args.export).unwrap();
+    }
+    if args.algorithm == Algorithm::PropertyDriven {
+        create_async_workers(&args);
+    }
+    println!("aiger smc begin");
+    let start = Instant::now();
+    let forward = lace_run(|_| fsmbdd.reachable_from_init());
+    let mut res = true;
+    for (i, bad) in aigbdd.bads.iter().enumerate() {
+        let bad_res = match args.algorithm {
+            Algorithm::PropertyDriven => {
+                fsmbdd.justice.clear();
+                let ba = BuchiAutomata::from_bad(&manager, &fsmbdd.symbols, bad);
+                PPSMC::new(
+                    manager.clone(),
+                    fsmbdd.clone(),
+                    ba,
+                    args.clone(),
+                    forward.clone(),
+                )
+                .check()
+            }
+            _ => (&forward & bad).is_constant(false),
+        };
+        println!("bad {}: {}", i, bad_res);
+        res &= bad_res;
+    }
+    for (i, justice) in aigbdd.justice.iter().enumerate() {
+        let mut constraints = aigbdd.fairness.clone();
+        constraints.extend(justice.iter().cloned());
+        let justice_res = match args.algorithm {
+            Algorithm::PropertyDriven => {
+                fsmbdd.justice.clear();
+                let ba = BuchiAutomata::from_justice(&manager, &fsmbdd.symbols, &constraints);
+                PPSMC::new(
+                    manager.clone(),
+                    fsmbdd.clone(),
+                    ba,
+                    args.clone(),
+                    forward.clone(),
+                )
+                .check()
+            }
+            Algorithm::KLiveness => {
+                fsmbdd.justice = constraints;
+                lace_run(|_| fsmbdd.k_liveness(&forward)).is_some()
+            }
+            _ => {
+                fsmbdd.justice = constraints;
+                lace_run(|_| fsmbdd.fair_cycle_with_algorithm(&forward, args.fair_algorithm.into()))
+                    .is_constant(false)
+            }
+        };
+        println!("justice {}: {}", i, justice_res);
+        res &= justice_res;
+    }
+    (res, start.elapsed())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check;
+    use crate::{command::Args, BddManager as Manager};
+    use aiger::Aig;
+    use bdds::BddManager;
+    use clap::Parser;
+
+    // latch `q` is set by input `enable` when clear and cleared right after, the fairness is
+    // `!enable`
+    const BADS: &str = "aag 3 1 1 0 1 2 0 0 0\n2\n4 6\n0\n4\n6 5 2\n";
+    const JUSTICE: &str = "aag 3 1 1 0 1 0 0 2 1\n2\n4 6\n1\n1\n0\n4\n3\n6 5 2\n";
+    const HOLDS: &str = "aag 3 1 1 0 1 1 0 1 1\n2\n4 6\n0\n1\n0\n3\n6 5 2\n";
+
+    fn check_circuit(circuit: &str, algorithm: &str) -> bool {
+        let manager = <Manager as BddManager>::new();
+        let aig = Aig::parse(circuit.as_bytes());
+        let args = Args::parse_from(["ppsmc", "--op", "-a", algorithm]);
+        check(manager, aig, args).0
+    }
+
+    #[test]
+    fn test_every_property() {
+        for algorithm in ["property-driven", "traditional", "k-liveness"] {
+            // only the second bad state `q` is reachable
+            assert!(!check_circuit(BADS, algorithm), "{}", algorithm);
+            // only the second justice property `q` has a fair cycle
+            assert!(!check_circuit(JUSTICE, algorithm), "{}", algorithm);
+            assert!(check_circuit(HOLDS, algorithm), "{}", algorithm);
+        }
+    }
+}
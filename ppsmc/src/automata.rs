@@ -1,6 +1,5 @@
-use crate::{Bdd, BddManager};
+use bdds::{Bdd, BddManager};
 use fsmbdd::{FsmBdd, Trans, TransBddMethod};
-use logic_form::Expr;
 use nom::{
     bytes::complete::{tag, take_until},
     character::complete::{line_ending, multispace0, space0},
@@ -8,20 +7,44 @@ use nom::{
     sequence::{delimited, terminated},
     IResult,
 };
-use std::{collections::HashMap, mem::take, process::Command};
+use smv::{Dialect, Expr};
+use std::{
+    collections::HashMap,
+    mem::take,
+    ops::{BitAnd, BitOr, BitXor, Not},
+    process::Command,
+};
 
 #[derive(Debug, Clone)]
-pub struct BuchiAutomata {
-    pub manager: BddManager,
+pub struct BuchiAutomata<BM: BddManager>
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    pub manager: BM,
     pub symbols: HashMap<String, usize>,
-    pub forward: Vec<Vec<(usize, Bdd)>>,
-    pub backward: Vec<Vec<(usize, Bdd)>>,
+    pub forward: Vec<Vec<(usize, BM::Bdd)>>,
+    pub backward: Vec<Vec<(usize, BM::Bdd)>>,
     pub accepting_states: Vec<usize>,
     pub init_states: Vec<usize>,
 }
 
-impl BuchiAutomata {
-    fn new(manager: BddManager) -> Self {
+impl<BM: BddManager> BuchiAutomata<BM>
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    pub fn new(manager: BM) -> Self {
         Self {
             symbols: HashMap::new(),
             manager,
@@ -43,7 +66,7 @@ impl BuchiAutomata {
         }
     }
 
-    pub fn add_edge(&mut self, from: usize, to: usize, label: Bdd) {
+    pub fn add_edge(&mut self, from: usize, to: usize, label: BM::Bdd) {
         self.extend_to(from);
         self.extend_to(to);
         assert!(from < self.num_state());
@@ -89,7 +112,16 @@ fn parse_state(input: &str) -> IResult<&str, (&str, Vec<(&str, &str)>)> {
     Ok((input, (ident, trans)))
 }
 
-impl BuchiAutomata {
+impl<BM: BddManager> BuchiAutomata<BM>
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
     fn state_ident_get_id<'a>(
         &mut self,
         map: &mut HashMap<&'a str, usize>,
@@ -105,7 +137,7 @@ impl BuchiAutomata {
             .unwrap()
     }
 
-    fn parse(input: &str, manager: &BddManager, symbols: &HashMap<String, Bdd>) -> Self {
+    fn parse(input: &str, manager: &BM, symbols: &HashMap<String, BM::Bdd>) -> Self {
         let mut ret = Self::new(manager.clone());
         let mut state_map = HashMap::new();
         let (input, _) = skip_line(input).unwrap();
@@ -120,18 +152,11 @@ impl BuchiAutomata {
             if ident.ends_with("_init") {
                 ret.add_init_state(state_id);
             }
-            let mut cond = manager.constant(true);
             for i in 0..trans.len() {
                 let edge = trans[i].0;
                 let dist = trans[i].1;
                 let dist = ret.state_ident_get_id(&mut state_map, dist);
-                let edge = if edge == "(1)" {
-                    Expr::LitExpr(true)
-                } else {
-                    Expr::from(edge)
-                };
-                let edge_bdd = edge.to_bdd(manager, symbols);
-                cond &= !&edge_bdd;
+                let edge_bdd = Expr::parse(edge, Dialect::Spot).to_bdd(manager, symbols);
                 ret.add_edge(state_id, dist, edge_bdd);
             }
         }
@@ -139,21 +164,22 @@ impl BuchiAutomata {
     }
 
     pub fn from_ltl(
-        ltl: smv::Expr,
-        manager: &BddManager,
+        ltl: Expr,
+        manager: &BM,
         symbols: &HashMap<String, usize>,
-        defines: &HashMap<String, Bdd>,
+        defines: &HashMap<String, BM::Bdd>,
     ) -> Self {
-        println!("'{}'", ltl);
+        let spot = ltl.display(Dialect::Spot).to_string();
+        println!("'{}'", spot);
         // let ltl2dfa = Command::new("./ltl2ba/ltl2ba")
         //     .arg("-f")
-        //     .arg(format!("{}", ltl))
+        //     .arg(ltl.display(Dialect::Ltl2ba).to_string())
         //     .output()
         //     .unwrap();
         let ltl2dfa = Command::new("/root/spot-2.11.5/bin/ltl2tgba")
             .arg("-s")
             .arg("-f")
-            .arg(format!("{}", ltl))
+            .arg(spot)
             .output()
             .unwrap();
         let ba = String::from_utf8_lossy(&ltl2dfa.stdout);
@@ -167,7 +193,39 @@ impl BuchiAutomata {
         ba
     }
 
-    fn automata_state_encode(&self, base: usize, num_encode_var: usize, mut id: usize) -> Bdd {
+    /// The automaton of `F bad`, the negation of the invariant `G !bad`.
+    pub fn from_bad(manager: &BM, symbols: &HashMap<String, usize>, bad: &BM::Bdd) -> Self {
+        let mut ba = Self::new(manager.clone());
+        ba.symbols = symbols.clone();
+        ba.add_edge(0, 0, !bad);
+        ba.add_edge(0, 1, bad.clone());
+        ba.add_edge(1, 1, manager.constant(true));
+        ba.add_init_state(0);
+        ba.add_accepting_state(1);
+        ba
+    }
+
+    /// The automaton of `G F j` for every `j` of `justice`, degeneralized by waiting for the
+    /// constraints in turn. State `i` waits for `justice[i]` and the last state is accepting.
+    pub fn from_justice(
+        manager: &BM,
+        symbols: &HashMap<String, usize>,
+        justice: &[BM::Bdd],
+    ) -> Self {
+        let mut ba = Self::new(manager.clone());
+        ba.symbols = symbols.clone();
+        for (i, j) in justice.iter().enumerate() {
+            ba.add_edge(i, i, !j);
+            ba.add_edge(i, i + 1, j.clone());
+        }
+        let accepting = justice.len();
+        ba.add_edge(accepting, 0, manager.constant(true));
+        ba.add_init_state(0);
+        ba.add_accepting_state(accepting);
+        ba
+    }
+
+    fn automata_state_encode(&self, base: usize, num_encode_var: usize, mut id: usize) -> BM::Bdd {
         let mut res = self.manager.constant(true);
         for i in 0..num_encode_var {
             let var = self.manager.ith_var((base + i) * 2);
@@ -181,7 +239,7 @@ impl BuchiAutomata {
         res
     }
 
-    pub fn to_fsmbdd(&self) -> FsmBdd<BddManager> {
+    pub fn to_fsmbdd(&self) -> FsmBdd<BM> {
         let mut symbols = self.symbols.clone();
         let base = symbols.len();
         let num_encode_var = usize::BITS as usize - (self.num_state() - 1).leading_zeros() as usize;
@@ -219,8 +277,14 @@ impl BuchiAutomata {
         }
     }
 
-    pub fn partition(mut self, var: usize) -> Self {
-        let var = var * 2;
+    pub fn partition(self, var: usize) -> Self {
+        let split = self.manager.ith_var(var * 2);
+        self.partition_by(&split)
+    }
+
+    /// Doubles the automaton, the states of the second copy are only entered by model states in
+    /// `split`, the first copy by the others. `split` may be any predicate, e.g. a cube.
+    pub fn partition_by(mut self, split: &BM::Bdd) -> Self {
         let num_states = self.num_state();
         for accept in self.accepting_states.clone() {
             self.accepting_states.push(accept + num_states);
@@ -232,16 +296,361 @@ impl BuchiAutomata {
         let forward = take(&mut self.forward);
         for i in 0..num_states {
             for (next, bdd) in forward[i].iter() {
-                self.add_edge(i, *next, bdd & !self.manager.ith_var(var));
-                self.add_edge(i + num_states, *next, bdd & !self.manager.ith_var(var));
-                self.add_edge(i, next + num_states, bdd & self.manager.ith_var(var));
-                self.add_edge(
-                    i + num_states,
-                    next + num_states,
-                    bdd & self.manager.ith_var(var),
-                );
+                self.add_edge(i, *next, bdd & !split);
+                self.add_edge(i + num_states, *next, bdd & !split);
+                self.add_edge(i, next + num_states, bdd & split);
+                self.add_edge(i + num_states, next + num_states, bdd & split);
             }
         }
         self
     }
+
+    /// Rebuilds the automaton with state `i` renamed to `map[i]`, removed if `None`. Edges
+    /// between the same states are merged and edges labeled `false` dropped.
+    fn rebuild(&self, map: &[Option<usize>]) -> Self {
+        let mut res = Self::new(self.manager.clone());
+        res.symbols = self.symbols.clone();
+        let num_state = map
+            .iter()
+            .flatten()
+            .map(|state| state + 1)
+            .max()
+            .unwrap_or(0);
+        let mut forward: Vec<Vec<(usize, BM::Bdd)>> = vec![Vec::new(); num_state];
+        for (from, edges) in self.forward.iter().enumerate() {
+            let Some(from) = map[from] else {
+                continue;
+            };
+            for (to, label) in edges.iter() {
+                let Some(to) = map[*to] else {
+                    continue;
+                };
+                if label.is_constant(false) {
+                    continue;
+                }
+                match forward[from].iter_mut().find(|(next, _)| *next == to) {
+                    Some((_, merged)) => *merged |= label,
+                    None => forward[from].push((to, label.clone())),
+                }
+            }
+        }
+        if num_state > 0 {
+            res.extend_to(num_state - 1);
+        }
+        for (from, edges) in forward.into_iter().enumerate() {
+            for (to, label) in edges {
+                res.add_edge(from, to, label);
+            }
+        }
+        for init in self.init_states.iter().filter_map(|state| map[*state]) {
+            if !res.init_states.contains(&init) {
+                res.add_init_state(init);
+            }
+        }
+        for accept in self.accepting_states.iter().filter_map(|state| map[*state]) {
+            if !res.accepting_states.contains(&accept) {
+                res.add_accepting_state(accept);
+            }
+        }
+        res
+    }
+
+    fn search(&self, from: &[usize], forward: bool) -> Vec<bool> {
+        let mut visit = vec![false; self.num_state()];
+        let mut stack = from.to_vec();
+        while let Some(state) = stack.pop() {
+            if visit[state] {
+                continue;
+            }
+            visit[state] = true;
+            let edges = if forward {
+                &self.forward[state]
+            } else {
+                &self.backward[state]
+            };
+            for (next, label) in edges.iter() {
+                if !visit[*next] && !label.is_constant(false) {
+                    stack.push(*next);
+                }
+            }
+        }
+        visit
+    }
+
+    fn tarjan(
+        &self,
+        state: usize,
+        index: &mut Vec<Option<(usize, usize)>>,
+        stack: &mut Vec<usize>,
+        scc: &mut Vec<usize>,
+        num_scc: &mut usize,
+    ) {
+        let id = index.iter().flatten().count();
+        index[state] = Some((id, id));
+        stack.push(state);
+        for (next, label) in self.forward[state].iter() {
+            if label.is_constant(false) {
+                continue;
+            }
+            match index[*next] {
+                None => {
+                    self.tarjan(*next, index, stack, scc, num_scc);
+                    let low = index[*next].unwrap().1;
+                    let (id, old) = index[state].unwrap();
+                    index[state] = Some((id, old.min(low)));
+                }
+                Some((next_id, _)) if stack.contains(next) => {
+                    let (id, old) = index[state].unwrap();
+                    index[state] = Some((id, old.min(next_id)));
+                }
+                _ => (),
+            }
+        }
+        let (id, low) = index[state].unwrap();
+        if id == low {
+            loop {
+                let member = stack.pop().unwrap();
+                scc[member] = *num_scc;
+                if member == state {
+                    break;
+                }
+            }
+            *num_scc += 1;
+        }
+    }
+
+    /// The strongly connected components, in reverse topological order.
+    pub fn sccs(&self) -> Vec<Vec<usize>> {
+        let num_state = self.num_state();
+        let mut index = vec![None; num_state];
+        let mut stack = Vec::new();
+        let mut scc = vec![0; num_state];
+        let mut num_scc = 0;
+        for state in 0..num_state {
+            if index[state].is_none() {
+                self.tarjan(state, &mut index, &mut stack, &mut scc, &mut num_scc);
+            }
+        }
+        let mut res = vec![Vec::new(); num_scc];
+        for state in 0..num_state {
+            res[scc[state]].push(state);
+        }
+        res
+    }
+
+    /// Whether an infinite run can stay in `scc`.
+    pub fn is_cyclic(&self, scc: &[usize]) -> bool {
+        scc.len() > 1
+            || self.forward[scc[0]]
+                .iter()
+                .any(|(next, label)| *next == scc[0] && !label.is_constant(false))
+    }
+
+    /// States that are reachable from the initial states and can reach an accepting cycle.
+    fn prune_states(&self) -> Self {
+        let num_state = self.num_state();
+        let accepting_scc: Vec<usize> = self
+            .sccs()
+            .into_iter()
+            .filter(|scc| {
+                self.is_cyclic(scc)
+                    && scc
+                        .iter()
+                        .any(|state| self.accepting_states.contains(state))
+            })
+            .flatten()
+            .collect();
+        let reach = self.search(&self.init_states, true);
+        let coreach = self.search(&accepting_scc, false);
+        let mut map = vec![None; num_state];
+        let mut num_keep = 0;
+        for state in 0..num_state {
+            if reach[state] && coreach[state] {
+                map[state] = Some(num_keep);
+                num_keep += 1;
+            }
+        }
+        if num_keep == 0 {
+            // empty language, keep one initial state if there is any
+            if let Some(init) = self.init_states.first() {
+                map[*init] = Some(0);
+            }
+        }
+        self.rebuild(&map)
+    }
+
+    /// `sim[q][p]` holds if `p` directly simulates `q`.
+    fn direct_simulation(&self) -> Vec<Vec<bool>> {
+        let num_state = self.num_state();
+        let accepting: Vec<bool> = (0..num_state)
+            .map(|state| self.accepting_states.contains(&state))
+            .collect();
+        let mut sim: Vec<Vec<bool>> = (0..num_state)
+            .map(|q| {
+                (0..num_state)
+                    .map(|p| !accepting[q] || accepting[p])
+                    .collect()
+            })
+            .collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for q in 0..num_state {
+                for p in 0..num_state {
+                    if q == p || !sim[q][p] {
+                        continue;
+                    }
+                    let simulated = self.forward[q].iter().all(|(q_next, label)| {
+                        let mut cover = self.manager.constant(false);
+                        for (p_next, p_label) in self.forward[p].iter() {
+                            if sim[*q_next][*p_next] {
+                                cover |= p_label;
+                            }
+                        }
+                        (label & !cover).is_constant(false)
+                    });
+                    if !simulated {
+                        sim[q][p] = false;
+                        changed = true;
+                    }
+                }
+            }
+        }
+        sim
+    }
+
+    /// Merges the states that directly simulate each other. Bisimilar states simulate each
+    /// other, so this subsumes bisimulation reduction.
+    fn merge_simulation_equivalent(&self) -> Self {
+        let sim = self.direct_simulation();
+        let mut map = vec![None; self.num_state()];
+        let mut num_class = 0;
+        for state in 0..self.num_state() {
+            let class = (0..state).find(|other| sim[state][*other] && sim[*other][state]);
+            map[state] = match class {
+                Some(other) => map[other],
+                None => {
+                    num_class += 1;
+                    Some(num_class - 1)
+                }
+            };
+        }
+        self.rebuild(&map)
+    }
+
+    /// Simplifies the automaton for the product with a model whose states all satisfy
+    /// `invariants`. Labels are decided by the invariants if possible, parallel edges are
+    /// merged, useless states are removed and simulation equivalent states are merged.
+    pub fn simplify(&self, invariants: &BM::Bdd) -> Self {
+        let mut res = self.clone();
+        for edges in res.forward.iter_mut().chain(res.backward.iter_mut()) {
+            for (_, label) in edges.iter_mut() {
+                if (&*label & invariants).is_constant(false) {
+                    *label = self.manager.constant(false);
+                } else if (!&*label & invariants).is_constant(false) {
+                    *label = self.manager.constant(true);
+                }
+            }
+        }
+        loop {
+            let num_state = res.num_state();
+            res = res.prune_states().merge_simulation_equivalent();
+            if res.num_state() == num_state {
+                break res;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BuchiAutomata;
+    use crate::BddManager as Manager;
+    use bdds::BddManager;
+
+    #[test]
+    fn test_merge_edges() {
+        let manager = <Manager as BddManager>::new();
+        let a = manager.ith_var(0);
+        let mut ba = BuchiAutomata::new(manager.clone());
+        ba.add_edge(0, 0, a.clone());
+        ba.add_edge(0, 0, !&a);
+        ba.add_init_state(0);
+        ba.add_accepting_state(0);
+        let ba = ba.simplify(&manager.constant(true));
+        assert_eq!(ba.num_state(), 1);
+        assert_eq!(ba.forward[0], vec![(0, manager.constant(true))]);
+    }
+
+    #[test]
+    fn test_prune_states() {
+        let manager = <Manager as BddManager>::new();
+        let a = manager.ith_var(0);
+        let mut ba = BuchiAutomata::new(manager.clone());
+        ba.add_edge(0, 0, a.clone());
+        // unreachable
+        ba.add_edge(1, 0, manager.constant(true));
+        // reachable, but no accepting cycle after it
+        ba.add_edge(0, 2, !&a);
+        ba.add_edge(2, 2, manager.constant(true));
+        ba.add_init_state(0);
+        ba.add_accepting_state(0);
+        let ba = ba.simplify(&manager.constant(true));
+        assert_eq!(ba.num_state(), 1);
+        assert_eq!(ba.init_states, vec![0]);
+        assert_eq!(ba.accepting_states, vec![0]);
+        assert_eq!(ba.forward[0], vec![(0, a)]);
+    }
+
+    #[test]
+    fn test_merge_simulation_equivalent() {
+        let manager = <Manager as BddManager>::new();
+        let a = manager.ith_var(0);
+        let mut ba = BuchiAutomata::new(manager.clone());
+        ba.add_edge(0, 1, a.clone());
+        ba.add_edge(0, 2, !&a);
+        ba.add_edge(1, 1, manager.constant(true));
+        ba.add_edge(2, 2, manager.constant(true));
+        ba.add_init_state(0);
+        ba.add_accepting_state(1);
+        ba.add_accepting_state(2);
+        let ba = ba.simplify(&manager.constant(true));
+        assert_eq!(ba.num_state(), 2);
+        assert_eq!(ba.forward[0], vec![(1, manager.constant(true))]);
+        assert_eq!(ba.forward[1], vec![(1, manager.constant(true))]);
+        assert_eq!(ba.accepting_states, vec![1]);
+    }
+
+    #[test]
+    fn test_strip_invariants() {
+        let manager = <Manager as BddManager>::new();
+        let a = manager.ith_var(0);
+        let b = manager.ith_var(2);
+        let mut ba = BuchiAutomata::new(manager.clone());
+        ba.add_edge(0, 0, b.clone());
+        ba.add_edge(0, 1, a.clone());
+        ba.add_edge(1, 1, manager.constant(true));
+        ba.add_init_state(0);
+        ba.add_accepting_state(0);
+        ba.add_accepting_state(1);
+        // `a` never holds and `b` always does
+        let ba = ba.simplify(&(!&a & &b));
+        assert_eq!(ba.num_state(), 1);
+        assert_eq!(ba.forward[0], vec![(0, manager.constant(true))]);
+    }
+
+    #[test]
+    fn test_empty_language() {
+        let manager = <Manager as BddManager>::new();
+        let mut ba = BuchiAutomata::new(manager.clone());
+        ba.add_edge(0, 1, manager.constant(true));
+        ba.add_edge(1, 1, manager.constant(true));
+        ba.add_init_state(0);
+        let simplified = ba.simplify(&manager.constant(true));
+        assert_eq!(simplified.num_state(), 1);
+        assert_eq!(simplified.init_states, vec![0]);
+        assert!(simplified.forward[0].is_empty());
+        ba.init_states.clear();
+        assert_eq!(ba.simplify(&manager.constant(true)).num_state(), 0);
+    }
 }
@@ -0,0 +1,222 @@
+use crate::command::Args;
+use bdds::BddManager;
+use cdcl::Solver;
+use logic_form::Lit;
+use smv::{cnf::SmvCnf, Expr, Infix, Prefix, Smv};
+use std::{
+    collections::HashMap,
+    ops::{BitAnd, BitOr, BitXor, Not},
+    time::{Duration, Instant},
+};
+
+/// Encodes an LTL formula on a lasso of frames `0..=bound` whose last frame loops back to
+/// `loop_start`. On a lasso every position has one successor, so each temporal operator is a
+/// disjunction or conjunction over the positions reachable from the current one.
+struct LassoEncoder<'a> {
+    unroller: &'a mut SmvCnf,
+    loop_start: usize,
+    bound: usize,
+    cache: HashMap<(*const Expr, usize), Lit>,
+}
+
+impl LassoEncoder<'_> {
+    /// Positions from `i` on, each once and in path order.
+    fn positions(&self, i: usize) -> Vec<usize> {
+        let mut positions: Vec<usize> = (i..=self.bound).collect();
+        positions.extend(self.loop_start..i);
+        positions
+    }
+
+    fn successor(&self, i: usize) -> usize {
+        if i < self.bound {
+            i + 1
+        } else {
+            self.loop_start
+        }
+    }
+
+    /// `left U right`, or its dual `left V right` with `release`.
+    fn until(&mut self, left: &Expr, right: &Expr, i: usize, release: bool) -> Lit {
+        let mut prefix = Lit::constant_lit(!release);
+        let mut res = Lit::constant_lit(release);
+        for position in self.positions(i) {
+            let right = self.encode(right, position);
+            let left = self.encode(left, position);
+            if release {
+                let hit = self.unroller.or(&[prefix, right]);
+                res = self.unroller.and(&[res, hit]);
+                prefix = self.unroller.or(&[prefix, left]);
+            } else {
+                let hit = self.unroller.and(&[prefix, right]);
+                res = self.unroller.or(&[res, hit]);
+                prefix = self.unroller.and(&[prefix, left]);
+            }
+        }
+        res
+    }
+
+    fn encode(&mut self, expr: &Expr, i: usize) -> Lit {
+        let key = (expr as *const Expr, i);
+        if let Some(lit) = self.cache.get(&key) {
+            return *lit;
+        }
+        let lit = match expr {
+            Expr::PrefixExpr(op, sub_expr) => match op {
+                Prefix::Not => !self.encode(sub_expr, i),
+                Prefix::LtlNext => self.encode(sub_expr, self.successor(i)),
+                Prefix::LtlGlobally | Prefix::LtlFinally => {
+                    let lits: Vec<Lit> = self
+                        .positions(i)
+                        .into_iter()
+                        .map(|position| self.encode(sub_expr, position))
+                        .collect();
+                    if let Prefix::LtlGlobally = op {
+                        self.unroller.and(&lits)
+                    } else {
+                        self.unroller.or(&lits)
+                    }
+                }
+                Prefix::LtlOnce => panic!("past operators are not supported by bmc"),
+                Prefix::Next => self.unroller.encode(expr, i),
+            },
+            Expr::InfixExpr(op, left, right) => match op {
+                Infix::LtlUntil => self.until(left, right, i, false),
+                Infix::LtlRelease => self.until(left, right, i, true),
+                Infix::LtlSince => panic!("past operators are not supported by bmc"),
+                _ => {
+                    let left = self.encode(left, i);
+                    let right = self.encode(right, i);
+                    match op {
+                        Infix::And => self.unroller.and(&[left, right]),
+                        Infix::Or => self.unroller.or(&[left, right]),
+                        Infix::Xor => self.unroller.xor(left, right),
+                        Infix::Imply => self.unroller.or(&[!left, right]),
+                        Infix::Iff => !self.unroller.xor(left, right),
+                        _ => unreachable!(),
+                    }
+                }
+            },
+            _ => self.unroller.encode(expr, i),
+        };
+        self.cache.insert(key, lit);
+        lit
+    }
+}
+
+/// Searches a lasso of at most `bound` transitions that satisfies the fairness constraints and
+/// violates the LTL spec `spec`, for every length and loop position.
+fn check_spec(smv: &Smv, spec: usize, args: &Args) -> bool {
+    let spec_expr = !smv.flatten_to_propositional_define(&smv.ltlspecs[spec]);
+    let mut unroller = SmvCnf::new(smv);
+    let mut solver = Solver::new();
+    let init = unroller.init();
+    solver.add_clause(&[init]);
+    for bound in 0..args.bmc_bound {
+        unroller.unroll();
+        for loop_start in 0..=bound {
+            let back = unroller.state_equal(bound + 1, loop_start);
+            let mut encoder = LassoEncoder {
+                unroller: &mut unroller,
+                loop_start,
+                bound,
+                cache: HashMap::new(),
+            };
+            let mut lasso = vec![back, encoder.encode(&spec_expr, 0)];
+            for fair in smv.fairness.iter() {
+                let lits: Vec<Lit> = (loop_start..=bound)
+                    .map(|position| encoder.encode(fair, position))
+                    .collect();
+                lasso.push(encoder.unroller.or(&lits));
+            }
+            let lasso = unroller.and(&lasso);
+            solver.add_cnf(&unroller.take_cnf());
+            if solver.solve(&[lasso]) {
+                println!(
+                    "bmc: ltlspec {} has a counterexample of length {} looping back to {}",
+                    spec,
+                    bound + 1,
+                    loop_start
+                );
+                return false;
+            }
+        }
+        if args.verbose {
+            println!(
+                "bmc: ltlspec {} has no counterexample of length {}, {} vars, {} conflicts",
+                spec,
+                bound + 1,
+                unroller.num_var(),
+                solver.num_conflict
+            );
+        }
+    }
+    println!(
+        "bmc: ltlspec {} has no counterexample within bound {}",
+        spec, args.bmc_bound
+    );
+    true
+}
+
+/// Bounded model checking of every LTL spec. Only counterexamples are conclusive, a spec is
+/// reported `true` if it has none within `--bmc-bound`.
+pub fn check<BM: BddManager>(_manager: BM, smv: Smv, args: Args) -> (bool, Duration)
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    let start = Instant::now();
+    let mut res = true;
+    for spec in 0..smv.ltlspecs.len() {
+        let spec_res = check_spec(&smv, spec, &args);
+        println!("ltlspec {}: {}", spec, spec_res);
+        res &= spec_res;
+    }
+    (res, start.elapsed())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check;
+    use crate::{command::Args, BddManager as Manager};
+    use bdds::BddManager;
+    use clap::Parser;
+    use smv::Smv;
+
+    /// Counts 0, 1, 2 and back to 0 in `b1 b0`, 3 is unreachable.
+    const COUNTER: &str = "MODULE main
+VAR
+b0 : boolean;
+b1 : boolean;
+INIT
+!b0 & !b1
+TRANS
+next(b0) <-> (!b0 & !b1)
+TRANS
+next(b1) <-> (b0 & !b1)
+";
+
+    fn check_one(spec: &str) -> bool {
+        let smv = Smv::parse(&format!("{}LTLSPEC {}\n", COUNTER, spec));
+        let args = Args::parse_from(["ppsmc", "--bmc-bound", "6"]);
+        check(<Manager as BddManager>::new(), smv, args).0
+    }
+
+    #[test]
+    fn test_counter() {
+        assert!(check_one("G !(b0 & b1)"));
+        assert!(!check_one("G !b1"));
+    }
+
+    #[test]
+    fn test_every_spec() {
+        let specs = "LTLSPEC G !(b0 & b1)\nLTLSPEC G !b1\n";
+        let smv = Smv::parse(&format!("{}{}", COUNTER, specs));
+        let args = Args::parse_from(["ppsmc", "--bmc-bound", "6"]);
+        assert!(!check(<Manager as BddManager>::new(), smv, args).0);
+    }
+}
@@ -1,16 +1,21 @@
 use clap::{Parser, ValueEnum};
-use fsmbdd::TransBddMethod;
+use fsmbdd::{FairCycleAlgorithm, TransBddMethod};
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
 pub enum TransMethod {
     Monolithic,
     Partition,
+    Saturation,
+    Disjunctive,
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
 pub enum Algorithm {
     PropertyDriven,
     Traditional,
+    Bmc,
+    Ic3,
+    KLiveness,
 }
 
 impl Into<TransBddMethod> for TransMethod {
@@ -18,6 +23,29 @@ impl Into<TransBddMethod> for TransMethod {
         match self {
             TransMethod::Monolithic => TransBddMethod::Monolithic,
             TransMethod::Partition => TransBddMethod::Partition,
+            TransMethod::Saturation => TransBddMethod::Saturation,
+            TransMethod::Disjunctive => TransBddMethod::Disjunctive,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
+pub enum FairAlgorithm {
+    EmersonLei,
+    EmersonLeiChaining,
+    Owcty,
+    Lockstep,
+    XieBeerel,
+}
+
+impl From<FairAlgorithm> for FairCycleAlgorithm {
+    fn from(algorithm: FairAlgorithm) -> Self {
+        match algorithm {
+            FairAlgorithm::EmersonLei => FairCycleAlgorithm::EmersonLei,
+            FairAlgorithm::EmersonLeiChaining => FairCycleAlgorithm::EmersonLeiChaining,
+            FairAlgorithm::Owcty => FairCycleAlgorithm::Owcty,
+            FairAlgorithm::Lockstep => FairCycleAlgorithm::Lockstep,
+            FairAlgorithm::XieBeerel => FairCycleAlgorithm::XieBeerel,
         }
     }
 }
@@ -25,7 +53,7 @@ impl Into<TransBddMethod> for TransMethod {
 #[derive(Parser, Debug, Clone)]
 /// Property-driven Symbolic Model Checking
 pub struct Args {
-    /// input file
+    /// input file, smv or aiger (aag/aig)
     #[arg(short, long, default_value_t)]
     pub file: String,
 
@@ -37,10 +65,35 @@ pub struct Args {
     #[arg(short = 'm', long, value_enum, default_value_t = TransMethod::Monolithic)]
     pub trans_method: TransMethod,
 
+    /// node limit of a trans cluster
+    #[arg(long, default_value_t = fsmbdd::DEFAULT_CLUSTER_THRESHOLD)]
+    pub cluster_threshold: usize,
+
+    /// write the model, as encoded for every engine, as an aiger (aag/aig) or blif circuit
+    #[arg(long, default_value_t)]
+    pub export: String,
+
+    /// write the product of the model and the automaton of each spec checked by the
+    /// traditional or the k-liveness engine, the spec index is appended to the file stem
+    #[arg(long, default_value_t)]
+    pub export_product: String,
+
+    /// bound of bmc
+    #[arg(long, default_value_t = 20)]
+    pub bmc_bound: usize,
+
+    /// fair cycle detection algorithm
+    #[arg(long, value_enum, default_value_t = FairAlgorithm::EmersonLei)]
+    pub fair_algorithm: FairAlgorithm,
+
     /// parallel
     #[arg(short, long, default_value_t = 1)]
     pub parallel: usize,
 
+    /// compute images as parallel tasks (not with cudd)
+    #[arg(long, default_value_t = false)]
+    pub parallel_image: bool,
+
     /// extend trans
     #[arg(short = 'e', long)]
     pub ltl_extend_trans: Vec<usize>,
@@ -49,6 +102,22 @@ pub struct Args {
     #[arg(long = "ev")]
     pub ltl_extend_vars: Vec<usize>,
 
+    /// split automaton states automatically to feed the parallel workers
+    #[arg(long, default_value_t = false)]
+    pub auto_partition: bool,
+
+    /// stop forward reachability at the first accepting lasso of a weak automaton scc
+    #[arg(long, default_value_t = false)]
+    pub on_the_fly: bool,
+
+    /// check passing ltl specs for vacuity with the property-driven engine
+    #[arg(long, default_value_t = false)]
+    pub vacuity: bool,
+
+    /// only warn when the model has no initial state or no fair path
+    #[arg(long, default_value_t = false)]
+    pub allow_unfair: bool,
+
     /// verbose
     #[arg(short, long, default_value_t = false)]
     pub verbose: bool,
@@ -61,7 +130,12 @@ pub struct Args {
     #[arg(long = "ga", default_value_t = false)]
     pub generalize_automata: bool,
 
-    /// flatten define
+    /// expand the defines and the case expressions before checking, shared subexpressions
+    /// become fresh defines
     #[arg(long = "fd", default_value_t = false)]
     pub flatten_define: bool,
+
+    /// dynamic variable reordering (cudd only)
+    #[arg(long, default_value_t = false)]
+    pub reorder: bool,
 }
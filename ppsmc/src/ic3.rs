@@ -0,0 +1,381 @@
+use crate::command::Args;
+use bdds::BddManager;
+use cdcl::Solver;
+use logic_form::{Clause, Cube, Lit, Var};
+use smv::{cnf::SmvCnf, Expr, Prefix, Smv};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    ops::{BitAnd, BitOr, BitXor, Not},
+    time::{Duration, Instant},
+};
+
+fn is_state_expr(expr: &Expr) -> bool {
+    match expr {
+        Expr::Ident(_) | Expr::LitExpr(_) => true,
+        Expr::PrefixExpr(prefix, sub_expr) => {
+            matches!(prefix, Prefix::Not) && is_state_expr(sub_expr)
+        }
+        Expr::InfixExpr(infix, left, right) => {
+            !matches!(
+                infix,
+                smv::Infix::LtlUntil | smv::Infix::LtlRelease | smv::Infix::LtlSince
+            ) && is_state_expr(left)
+                && is_state_expr(right)
+        }
+        Expr::CaseExpr(case_expr) => case_expr
+            .branchs
+            .iter()
+            .all(|(cond, res)| is_state_expr(cond) && is_state_expr(res)),
+    }
+}
+
+/// IC3/PDR on one transition unrolling. `frames[i]` holds the cubes blocked at level `i` but
+/// not at `i + 1`, so frame `F_i` is the conjunction of the negated cubes of `frames[i..]`. The
+/// lemmas of level `i` are guarded by `act[i]`, `act[0]` guards the initial states. The
+/// transition is only assumed in relative induction queries, so states without successors are
+/// still checked against the property.
+struct Ic3 {
+    unroller: SmvCnf,
+    solver: Solver,
+    init_solver: Solver,
+    frames: Vec<Vec<Cube>>,
+    act: Vec<Lit>,
+    bad: Lit,
+    trans: Lit,
+    current: Vec<Lit>,
+    next: HashMap<Var, Lit>,
+    names: HashMap<Var, String>,
+}
+
+impl Ic3 {
+    fn new(smv: &Smv, property: &Expr) -> Self {
+        let mut unroller = SmvCnf::new(smv);
+        unroller.add_frame();
+        let trans = unroller.trans(0);
+        let init = unroller.init();
+        let bad = !unroller.encode(property, 0);
+        let init_act = unroller.new_var();
+        let current = unroller.state(0);
+        let next = current
+            .iter()
+            .zip(unroller.state(1))
+            .map(|(x, y)| (x.var(), y))
+            .collect();
+        let names = current
+            .iter()
+            .zip(smv.vars.iter())
+            .map(|(x, var)| (x.var(), var.ident.clone()))
+            .collect();
+        let cnf = unroller.take_cnf();
+        let mut solver = Solver::new();
+        solver.add_cnf(&cnf);
+        solver.add_clause(&[!init_act, init]);
+        let mut init_solver = Solver::new();
+        init_solver.add_cnf(&cnf);
+        init_solver.add_clause(&[init]);
+        Self {
+            unroller,
+            solver,
+            init_solver,
+            frames: vec![Vec::new()],
+            act: vec![init_act],
+            bad,
+            trans,
+            current,
+            next,
+            names,
+        }
+    }
+
+    fn level(&self) -> usize {
+        self.frames.len() - 1
+    }
+
+    fn new_frame(&mut self) {
+        let act = self.unroller.new_var();
+        self.act.push(act);
+        self.frames.push(Vec::new());
+    }
+
+    fn frame_assumptions(&self, level: usize) -> Vec<Lit> {
+        self.act[level..].to_vec()
+    }
+
+    fn prime(&self, lit: Lit) -> Lit {
+        let next = self.next[&lit.var()];
+        if lit.compl() {
+            !next
+        } else {
+            next
+        }
+    }
+
+    fn intersects_init(&mut self, cube: &Cube) -> bool {
+        self.init_solver.solve(cube)
+    }
+
+    /// Full assignment of the current-state variables in the last model.
+    fn model_cube(&self) -> Cube {
+        self.current
+            .iter()
+            .filter_map(|lit| {
+                self.solver
+                    .lit_value(*lit)
+                    .map(|v| if v { *lit } else { !*lit })
+            })
+            .collect()
+    }
+
+    /// Checks `F_level & !cube & T => !cube'`. On success returns the part of `cube` in the
+    /// unsatisfiable core, otherwise a predecessor of `cube` in `F_level`.
+    fn relative_inductive(&mut self, cube: &Cube, level: usize) -> Result<Cube, Cube> {
+        let temp = self.unroller.new_var();
+        // the activation literal goes first, the retired clause is then skipped right away
+        let mut clause = Clause::from([!temp]);
+        clause.extend(cube.iter().map(|lit| !*lit));
+        self.solver.add_clause(&clause);
+        let mut assumptions = self.frame_assumptions(level);
+        assumptions.push(temp);
+        assumptions.push(self.trans);
+        assumptions.extend(cube.iter().map(|lit| self.prime(*lit)));
+        let res = if self.solver.solve(&assumptions) {
+            Err(self.model_cube())
+        } else {
+            Ok(cube
+                .iter()
+                .filter(|lit| self.solver.failed(self.prime(**lit)))
+                .copied()
+                .collect::<Cube>())
+        };
+        self.solver.add_clause(&[!temp]);
+        match res {
+            Ok(core) if core.len() < cube.len() && !self.intersects_init(&core) => Ok(core),
+            Ok(_) => Ok(cube.clone()),
+            Err(pred) => Err(pred),
+        }
+    }
+
+    /// Drops literals while the cube stays disjoint from init and inductive relative to
+    /// `F_level`.
+    fn generalize(&mut self, mut cube: Cube, level: usize) -> Cube {
+        let mut i = 0;
+        while i < cube.len() {
+            let mut candidate = cube.clone();
+            candidate.remove(i);
+            if !self.intersects_init(&candidate) {
+                if let Ok(core) = self.relative_inductive(&candidate, level) {
+                    cube = core;
+                    continue;
+                }
+            }
+            i += 1;
+        }
+        cube
+    }
+
+    fn add_lemma(&mut self, cube: Cube, level: usize) {
+        for frame in self.frames[1..=level].iter_mut() {
+            frame.retain(|blocked| !cube.subsume(blocked));
+        }
+        let mut clause: Clause = cube.iter().map(|lit| !*lit).collect();
+        clause.push(!self.act[level]);
+        self.solver.add_clause(&clause);
+        self.frames[level].push(cube);
+    }
+
+    fn is_blocked(&self, cube: &Cube, level: usize) -> bool {
+        self.frames[level..]
+            .iter()
+            .flatten()
+            .any(|blocked| blocked.subsume(cube))
+    }
+
+    /// Blocks `cube` at `level`, returns false if it reaches the initial states.
+    fn block(&mut self, cube: Cube, level: usize) -> bool {
+        // lowest level first, the latest obligation among equal levels
+        let mut cubes = vec![cube];
+        let mut obligations = BinaryHeap::from([(Reverse(level), 0)]);
+        while let Some((Reverse(level), id)) = obligations.pop() {
+            let cube = cubes[id].clone();
+            if level == 0 {
+                return false;
+            }
+            if self.is_blocked(&cube, level) {
+                continue;
+            }
+            match self.relative_inductive(&cube, level - 1) {
+                Ok(core) => {
+                    let mut lemma = self.generalize(core, level - 1);
+                    let mut lemma_level = level;
+                    while lemma_level < self.level() {
+                        match self.relative_inductive(&lemma, lemma_level) {
+                            Ok(core) => lemma = core,
+                            Err(_) => break,
+                        }
+                        lemma_level += 1;
+                    }
+                    self.add_lemma(lemma, lemma_level);
+                    if lemma_level < self.level() {
+                        obligations.push((Reverse(lemma_level + 1), id));
+                    }
+                }
+                Err(pred) => {
+                    obligations.push((Reverse(level), id));
+                    obligations.push((Reverse(level - 1), cubes.len()));
+                    cubes.push(pred);
+                }
+            }
+        }
+        true
+    }
+
+    /// Pushes lemmas forward, returns the level whose frame became empty if any.
+    fn propagate(&mut self) -> Option<usize> {
+        for level in 1..self.level() {
+            for cube in self.frames[level].clone() {
+                if self.relative_inductive(&cube, level).is_ok() {
+                    self.frames[level].retain(|blocked| *blocked != cube);
+                    self.add_lemma(cube, level + 1);
+                }
+            }
+            if self.frames[level].is_empty() {
+                return Some(level);
+            }
+        }
+        None
+    }
+
+    fn print_invariant(&self, level: usize, verbose: bool) {
+        let lemmas: Vec<&Cube> = self.frames[level + 1..].iter().flatten().collect();
+        println!("ic3: inductive invariant with {} clauses", lemmas.len());
+        if !verbose {
+            return;
+        }
+        for cube in lemmas {
+            let clause: Vec<String> = cube
+                .iter()
+                .map(|lit| {
+                    let name = &self.names[&lit.var()];
+                    if lit.compl() {
+                        name.to_string()
+                    } else {
+                        format!("!{}", name)
+                    }
+                })
+                .collect();
+            println!("  {}", clause.join(" | "));
+        }
+    }
+
+    fn check(&mut self, verbose: bool) -> bool {
+        if self.init_solver.solve(&[self.bad]) {
+            return false;
+        }
+        self.new_frame();
+        loop {
+            loop {
+                let mut assumptions = self.frame_assumptions(self.level());
+                assumptions.push(self.bad);
+                if !self.solver.solve(&assumptions) {
+                    break;
+                }
+                let cube = self.model_cube();
+                if !self.block(cube, self.level()) {
+                    println!("ic3: counterexample at depth {}", self.level());
+                    return false;
+                }
+            }
+            self.new_frame();
+            if verbose {
+                let sizes: Vec<usize> = self.frames.iter().map(|frame| frame.len()).collect();
+                println!("ic3: frames {:?}", sizes);
+            }
+            if let Some(level) = self.propagate() {
+                self.print_invariant(level, verbose);
+                return true;
+            }
+        }
+    }
+}
+
+/// Checks every LTL spec of the form `G p` with IC3, the others are skipped. Counterexamples
+/// are not restricted to fair paths, so models with fairness constraints are refused.
+pub fn check<BM: BddManager>(_manager: BM, smv: Smv, args: Args) -> (bool, Duration)
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    assert!(
+        smv.fairness.is_empty(),
+        "ic3 does not support fairness constraints"
+    );
+    let start = Instant::now();
+    let mut res = true;
+    for spec in 0..smv.ltlspecs.len() {
+        let property = match smv.flatten_to_propositional_define(&smv.ltlspecs[spec]) {
+            Expr::PrefixExpr(Prefix::LtlGlobally, property) if is_state_expr(&property) => property,
+            _ => {
+                println!("ltlspec {}: skipped, ic3 only checks G p", spec);
+                continue;
+            }
+        };
+        let mut ic3 = Ic3::new(&smv, &property);
+        let spec_res = ic3.check(args.verbose);
+        println!("ltlspec {}: {}", spec, spec_res);
+        res &= spec_res;
+    }
+    (res, start.elapsed())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check;
+    use crate::{command::Args, BddManager as Manager};
+    use bdds::BddManager;
+    use clap::Parser;
+    use smv::Smv;
+
+    /// Counts 0, 1, 2 and back to 0 in `b1 b0`, 3 is unreachable.
+    const COUNTER: &str = "MODULE main
+VAR
+b0 : boolean;
+b1 : boolean;
+INIT
+!b0 & !b1
+TRANS
+next(b0) <-> (!b0 & !b1)
+TRANS
+next(b1) <-> (b0 & !b1)
+";
+
+    fn check_spec(spec: &str) -> bool {
+        let smv = Smv::parse(&format!("{}LTLSPEC {}\n", COUNTER, spec));
+        let args = Args::parse_from(["ppsmc"]);
+        check(<Manager as BddManager>::new(), smv, args).0
+    }
+
+    #[test]
+    fn test_counter() {
+        assert!(check_spec("G !(b0 & b1)"));
+        assert!(!check_spec("G !b1"));
+        // not an invariant, skipped
+        assert!(check_spec("F b1"));
+    }
+
+    #[test]
+    fn test_every_spec() {
+        assert!(!check_spec("G !(b0 & b1)\nLTLSPEC F b1\nLTLSPEC G !b1"));
+    }
+
+    #[test]
+    #[should_panic(expected = "fairness")]
+    fn test_refuse_fairness() {
+        check_spec("G !(b0 & b1)\nFAIRNESS b1");
+    }
+}
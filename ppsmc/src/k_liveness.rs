@@ -0,0 +1,47 @@
+use crate::{
+    command::Args,
+    lace::lace_run,
+    traditional::{ltl_product, model_fsmbdd, shared_model_reach},
+};
+use bdds::BddManager;
+use smv::Smv;
+use std::{
+    ops::{BitAnd, BitOr, BitXor, Not},
+    time::{Duration, Instant},
+};
+
+/// Checks the product of the model and the automaton with k-liveness, each bound `k` is an
+/// invariant discharged by forward reachability instead of a nested fair-cycle fixpoint.
+pub fn check<BM: BddManager>(manager: BM, smv: Smv, args: Args) -> (bool, Duration)
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    let (mut smvbdd, fsmbdd) = model_fsmbdd(&manager, &smv, &args);
+    let model_reach = shared_model_reach(&smv, &fsmbdd, &args);
+    let mut res = true;
+    let mut time = Duration::ZERO;
+    for spec in 0..smv.ltlspecs.len() {
+        let product = ltl_product(&mut smvbdd, &fsmbdd, &smv, spec, &args);
+        if args.verbose {
+            println!("k-liveness: {} justice constraints", product.justice.len());
+        }
+        println!("k-liveness smc begin");
+        let start = Instant::now();
+        let forward =
+            lace_run(|_| product.reachable_with_constrain(&product.init, true, true, &model_reach));
+        let spec_res = lace_run(|_| product.k_liveness(&forward));
+        time += start.elapsed();
+        match spec_res {
+            Some(k) => println!("k-liveness: ltlspec {} proved with k = {}", spec, k),
+            None => println!("k-liveness: ltlspec {} has a fair cycle", spec),
+        }
+        res &= spec_res.is_some();
+    }
+    (res, time)
+}
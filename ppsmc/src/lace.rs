@@ -0,0 +1,80 @@
+//! Task scheduling of the parallel algorithms. With the `sylvan` feature this is the Lace shipped
+//! with Sylvan, otherwise tasks run on the calling thread as soon as they are spawned and the
+//! non-blocking runs get a thread each.
+
+#[cfg(feature = "sylvan")]
+pub use sylvan::{lace_run, lace_run_without_block, LaceWorkerContext};
+
+/// Starts the scheduler with `num_worker` workers, Lace is started together with Sylvan.
+#[cfg(feature = "sylvan")]
+pub fn init(num_worker: usize) {
+    sylvan::Sylvan::init(num_worker);
+}
+
+#[cfg(not(feature = "sylvan"))]
+pub use sequential::*;
+
+#[cfg(not(feature = "sylvan"))]
+mod sequential {
+    use std::{any::Any, cell::RefCell, iter::repeat_with, thread};
+
+    thread_local! {
+        // results of the spawned tasks not synced yet, the last spawned on top
+        static RESULTS: RefCell<Vec<Box<dyn Any>>> = const { RefCell::new(Vec::new()) };
+    }
+
+    pub fn init(_num_worker: usize) {}
+
+    #[derive(Clone, Copy)]
+    pub struct LaceWorkerContext;
+
+    impl LaceWorkerContext {
+        pub fn lace_spawn<F, R>(&mut self, f: F)
+        where
+            F: FnOnce(LaceWorkerContext) -> R,
+            F: Send + 'static,
+            R: Send + 'static,
+        {
+            let res = f(Self);
+            RESULTS.with(|results| results.borrow_mut().push(Box::new(res)));
+        }
+
+        pub fn lace_sync<R>(&mut self) -> R
+        where
+            R: Send + 'static,
+        {
+            let res = RESULTS.with(|results| results.borrow_mut().pop().unwrap());
+            *res.downcast().unwrap()
+        }
+
+        pub fn lace_sync_multi<R>(&mut self, num: usize) -> Vec<R>
+        where
+            R: Send + 'static,
+        {
+            let mut res: Vec<R> = repeat_with(|| self.lace_sync::<R>()).take(num).collect();
+            res.reverse();
+            res
+        }
+
+        pub fn steal_random(&self) {
+            thread::yield_now();
+        }
+    }
+
+    pub fn lace_run<F, R>(f: F) -> R
+    where
+        F: FnOnce(LaceWorkerContext) -> R,
+        F: Send,
+        R: Send + 'static,
+    {
+        f(LaceWorkerContext)
+    }
+
+    pub fn lace_run_without_block<F>(f: F)
+    where
+        F: FnOnce(LaceWorkerContext),
+        F: Send + 'static,
+    {
+        thread::spawn(move || f(LaceWorkerContext));
+    }
+}
@@ -1,18 +1,30 @@
 #![feature(get_mut_unchecked)]
 
+mod aig;
 mod automata;
+mod bmc;
 mod command;
+mod ic3;
+mod k_liveness;
+mod lace;
 mod ltl;
 mod property_driven;
 mod traditional;
 mod util;
 
+use aiger::Aig;
 use clap::Parser;
 use command::Algorithm;
 use smv::Smv;
 
+#[cfg(feature = "cudd")]
+type BddManager = cudd::Cudd;
+#[cfg(all(feature = "peabody", not(feature = "cudd")))]
+type BddManager = peabody::Peabody;
+#[cfg(all(feature = "sylvan", not(any(feature = "cudd", feature = "peabody"))))]
 type BddManager = sylvan::Sylvan;
-type Bdd = sylvan::Bdd;
+#[cfg(not(any(feature = "cudd", feature = "peabody", feature = "sylvan")))]
+compile_error!("enable one of the BDD package features `sylvan`, `cudd` or `peabody`");
 
 fn main() {
     let input_file = "abp8-p0.smv";
@@ -22,11 +34,48 @@ fn main() {
     if !args.file.is_empty() {
         input_file = args.file.to_string();
     }
-    let smv = Smv::from_file(input_file).unwrap();
-    let manager = BddManager::init(args.parallel);
+    let is_aiger = input_file.ends_with(".aag") || input_file.ends_with(".aig");
+    lace::init(args.parallel);
+    let manager = <BddManager as bdds::BddManager>::new();
+    #[cfg(feature = "cudd")]
+    if args.reorder {
+        manager.enable_reordering();
+    }
+    #[cfg(feature = "cudd")]
+    assert!(!args.parallel_image, "cudd managers are not thread-safe");
+    #[cfg(not(feature = "sylvan"))]
+    assert!(
+        !args.parallel_image,
+        "parallel images need the lace scheduler of sylvan"
+    );
+    if <BddManager as bdds::BddManager>::SHARED_TABLE
+        && (args.algorithm == Algorithm::PropertyDriven || args.vacuity)
+    {
+        println!(
+            "warning: the bdd package has a single node table, the property-driven workers \
+             share it instead of working on a manager each"
+        );
+    }
+    if is_aiger {
+        let aig = Aig::from_file(input_file).unwrap();
+        let (res, time) = aig::check(manager, aig, args);
+        println!("res: {}, time: {:?}", res, time);
+        return;
+    }
+    let mut smv = Smv::from_file(input_file).unwrap();
+    if args.flatten_define {
+        smv = smv.flatten_defines();
+    }
+    if !args.export.is_empty() {
+        traditional::export_model(&manager, &smv, &args);
+    }
     let algorithm = match args.algorithm {
-        Algorithm::PropertyDriven => property_driven::check,
-        Algorithm::Traditional => traditional::check,
+        _ if args.vacuity => property_driven::vacuity::check::<BddManager>,
+        Algorithm::PropertyDriven => property_driven::check::<BddManager>,
+        Algorithm::Traditional => traditional::check::<BddManager>,
+        Algorithm::Bmc => bmc::check::<BddManager>,
+        Algorithm::Ic3 => ic3::check::<BddManager>,
+        Algorithm::KLiveness => k_liveness::check::<BddManager>,
     };
     let (res, time) = algorithm(manager, smv, args);
     println!("res: {}, time: {:?}", res, time);
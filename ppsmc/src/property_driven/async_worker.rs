@@ -1,9 +1,9 @@
+use crate::lace::{lace_run_without_block, LaceWorkerContext};
 use arun::async_worker_run;
 use std::{
     ops::SubAssign,
     sync::{Arc, Condvar, Mutex},
 };
-use sylvan::{lace_run_without_block, LaceWorkerContext};
 
 #[derive(Clone)]
 pub struct AsyncWorker {
@@ -1,9 +1,133 @@
 use super::PPSMC;
-use crate::Bdd;
-use sylvan::LaceWorkerContext;
+use crate::lace::LaceWorkerContext;
+use bdds::{Bdd, BddManager};
+use fsmbdd::{fair_cycle, fair_lasso, pick_state, FairCycleAlgorithm, FairGraph, Lasso};
+use std::ops::{BitAnd, BitOr, BitXor, Not};
 
-impl PPSMC {
-    pub fn fair_states(&mut self, mut context: LaceWorkerContext, init_reach: &[Bdd]) -> Vec<Bdd> {
+impl<BM: BddManager> PPSMC<BM>
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    /// Cyclic automaton SCCs with accepting states, split into weak SCCs (all states accepting)
+    /// and the others.
+    fn accepting_sccs(&self) -> (Vec<Vec<usize>>, Vec<Vec<usize>>) {
+        let mut weak = Vec::new();
+        let mut strong = Vec::new();
+        for scc in self.automata.sccs() {
+            if !self.automata.is_cyclic(&scc) {
+                continue;
+            }
+            let num_accepting = scc
+                .iter()
+                .filter(|state| self.automata.accepting_states.contains(state))
+                .count();
+            if num_accepting == scc.len() {
+                weak.push(scc);
+            } else if num_accepting > 0 {
+                strong.push(scc);
+            }
+        }
+        (weak, strong)
+    }
+
+    /// Prepares fair-cycle detection on the automaton SCCs. An accepting cycle never leaves an
+    /// SCC, so transient states and SCCs without accepting states are dropped from `reach`. In a
+    /// weak SCC every state is accepting, so it is decided here by a single greatest fixpoint
+    /// over one-step pre-images. Returns the reachable states of the remaining SCCs, which need
+    /// the nested fixpoint, and whether a weak SCC has a fair cycle.
+    pub fn fair_constraint(&self, reach: &[BM::Bdd]) -> (Vec<BM::Bdd>, bool) {
+        let mut constraint = vec![self.manager.constant(false); self.automata.num_state()];
+        let (weak, strong) = self.accepting_sccs();
+        if self.args.verbose {
+            println!(
+                "accepting sccs: {} weak, {} need nested fixpoint",
+                weak.len(),
+                strong.len()
+            );
+        }
+        let weak_fair = weak.iter().any(|scc| self.weak_fair_cycle(scc, reach));
+        for state in strong.into_iter().flatten() {
+            constraint[state] = reach[state].clone();
+        }
+        (constraint, weak_fair)
+    }
+
+    /// Forward reachability on the product that looks for accepting lassos of the weak SCCs
+    /// whenever new states enter them. Returns `None` as soon as one is found.
+    pub fn on_the_fly_post_reachable(&self, init: &[BM::Bdd]) -> Option<Vec<BM::Bdd>> {
+        let (weak, _) = self.accepting_sccs();
+        let mut reach = init.to_vec();
+        let mut frontier = reach.clone();
+        let model_reach = vec![self.model_reach.clone(); reach.len()];
+        let mut depth = 0;
+        loop {
+            for scc in weak.iter() {
+                if scc.iter().all(|state| frontier[*state].is_constant(false)) {
+                    continue;
+                }
+                if self.weak_fair_cycle(scc, &reach) {
+                    println!("on the fly: accepting lasso found at depth {}", depth);
+                    return None;
+                }
+            }
+            let image = self.and(&self.post_image(&frontier), &model_reach);
+            frontier = self.diff(&image, &reach);
+            if self.is_empty(&frontier) {
+                return Some(reach);
+            }
+            reach = self.or(&reach, &frontier);
+            depth += 1;
+        }
+    }
+
+    /// A fair lasso of the product, the counterexample of a failing check. Its states hold a
+    /// single model state at a single automaton state.
+    pub fn witness(&self) -> Option<Lasso<Vec<BM::Bdd>>> {
+        let mut init = self.empty();
+        for init_state in self.automata.init_states.iter() {
+            init[*init_state] |= &self.fsmbdd.init;
+        }
+        let model_reach = vec![self.model_reach.clone(); init.len()];
+        let reach = self.reachable(&init, true, &model_reach);
+        let fair = fair_cycle(self, &reach, FairCycleAlgorithm::EmersonLei);
+        fair_lasso(self, &init, &reach, &fair)
+    }
+
+    fn weak_fair_cycle(&self, scc: &[usize], reach: &[BM::Bdd]) -> bool {
+        let mut fair: Vec<BM::Bdd> = scc.iter().map(|state| reach[*state].clone()).collect();
+        loop {
+            let new_fair: Vec<BM::Bdd> = scc
+                .iter()
+                .enumerate()
+                .map(|(i, state)| {
+                    let mut pre = self.manager.constant(false);
+                    for (next, label) in self.automata.forward[*state].iter() {
+                        if let Some(j) = scc.iter().position(|s| s == next) {
+                            pre |= self.fsmbdd.pre_image(&fair[j]) & label;
+                        }
+                    }
+                    &fair[i] & pre
+                })
+                .collect();
+            if new_fair == fair {
+                break;
+            }
+            fair = new_fair;
+        }
+        fair.iter().any(|states| !states.is_constant(false))
+    }
+
+    pub fn fair_states(
+        &mut self,
+        context: LaceWorkerContext,
+        init_reach: &[BM::Bdd],
+    ) -> Vec<BM::Bdd> {
         let mut fair_states = vec![self.manager.constant(false); self.automata.num_state()];
         for state in self.automata.accepting_states.iter() {
             fair_states[*state] = init_reach[*state].clone();
@@ -15,12 +139,11 @@ impl PPSMC {
                 dbg!(x);
             }
             let backward = self.lace_pre_reachable(context, &fair_states, init_reach);
-            fair_states.iter().zip(backward.iter()).for_each(|(x, y)| {
-                let x = x.clone();
-                let y = y.clone();
-                context.lace_spawn(|_| x & y)
-            });
-            let new_fair_states: Vec<Bdd> = context.lace_sync_multi(fair_states.len());
+            let new_fair_states: Vec<BM::Bdd> = fair_states
+                .iter()
+                .zip(backward.iter())
+                .map(|(x, y)| x & y)
+                .collect();
             if fair_states == new_fair_states {
                 break;
             }
@@ -29,7 +152,7 @@ impl PPSMC {
         fair_states
     }
 
-    pub async fn async_fair_states(&mut self, init_reach: &[Bdd]) -> Vec<Bdd> {
+    pub async fn async_fair_states(&mut self, init_reach: &[BM::Bdd]) -> Vec<BM::Bdd> {
         let mut fair_states = vec![self.manager.constant(false); self.automata.num_state()];
         for state in self.automata.accepting_states.iter() {
             fair_states[*state] = init_reach[*state].clone();
@@ -51,3 +174,78 @@ impl PPSMC {
         fair_states
     }
 }
+
+/// The product of the model and the automaton, a set holds the model states of every automaton
+/// state and the accepting automaton states form the only justice constraint.
+impl<BM: BddManager> FairGraph for PPSMC<BM>
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    type Set = Vec<BM::Bdd>;
+
+    fn empty(&self) -> Vec<BM::Bdd> {
+        vec![self.manager.constant(false); self.automata.num_state()]
+    }
+
+    fn and(&self, a: &Vec<BM::Bdd>, b: &Vec<BM::Bdd>) -> Vec<BM::Bdd> {
+        a.iter().zip(b.iter()).map(|(x, y)| x & y).collect()
+    }
+
+    fn or(&self, a: &Vec<BM::Bdd>, b: &Vec<BM::Bdd>) -> Vec<BM::Bdd> {
+        a.iter().zip(b.iter()).map(|(x, y)| x | y).collect()
+    }
+
+    fn diff(&self, a: &Vec<BM::Bdd>, b: &Vec<BM::Bdd>) -> Vec<BM::Bdd> {
+        a.iter().zip(b.iter()).map(|(x, y)| x & !y).collect()
+    }
+
+    fn is_empty(&self, a: &Vec<BM::Bdd>) -> bool {
+        a.iter().all(|x| x.is_constant(false))
+    }
+
+    fn pre_image(&self, a: &Vec<BM::Bdd>) -> Vec<BM::Bdd> {
+        let images: Vec<BM::Bdd> = a.iter().map(|x| self.fsmbdd.pre_image(x)).collect();
+        self.automata
+            .forward
+            .iter()
+            .map(|edges| {
+                let mut pre = self.manager.constant(false);
+                for (next, label) in edges.iter() {
+                    pre |= &images[*next] & label;
+                }
+                pre
+            })
+            .collect()
+    }
+
+    fn post_image(&self, a: &Vec<BM::Bdd>) -> Vec<BM::Bdd> {
+        let mut post = self.empty();
+        for (state, edges) in self.automata.forward.iter().enumerate() {
+            for (next, label) in edges.iter() {
+                post[*next] |= self.fsmbdd.post_image(&(&a[state] & label));
+            }
+        }
+        post
+    }
+
+    fn justice(&self) -> Vec<Vec<BM::Bdd>> {
+        let mut accepting = self.empty();
+        for state in self.automata.accepting_states.iter() {
+            accepting[*state] = self.manager.constant(true);
+        }
+        vec![accepting]
+    }
+
+    fn pick(&self, a: &Vec<BM::Bdd>) -> Vec<BM::Bdd> {
+        let mut res = self.empty();
+        let state = a.iter().position(|x| !x.is_constant(false)).unwrap();
+        res[state] = pick_state(&self.manager, &a[state]);
+        res
+    }
+}
@@ -1,35 +1,66 @@
 mod async_worker;
 mod fair;
+mod partition;
 mod reachable;
 mod statistic;
+pub mod vacuity;
 mod worker;
 
 use self::{async_worker::AsyncWorker, statistic::Statistic, worker::Worker};
-use crate::{automata::BuchiAutomata, command::Args, ltl::ltl_to_automata_preprocess, BddManager};
+use crate::{
+    automata::BuchiAutomata,
+    command::{Args, FairAlgorithm},
+    lace::lace_run,
+    ltl::ltl_to_automata_preprocess,
+    traditional::{model_fsmbdd, shared_model_reach},
+};
 use arun::async_block_on;
+use bdds::BddManager;
 use fsmbdd::FsmBdd;
 use smv::{bdd::SmvBdd, Expr, Prefix, Smv};
 use std::{
+    ops::{BitAnd, BitOr, BitXor, Not},
     sync::Arc,
     time::{Duration, Instant},
 };
-use sylvan::{lace_run, Sylvan};
 
-pub struct PPSMC {
-    manager: BddManager,
-    fsmbdd: FsmBdd<BddManager>,
-    automata: BuchiAutomata,
+pub struct PPSMC<BM: BddManager>
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    manager: BM,
+    fsmbdd: FsmBdd<BM>,
+    automata: BuchiAutomata<BM>,
     args: Args,
     statistic: Statistic,
-    workers: Vec<Arc<Worker>>,
+    workers: Vec<Arc<Worker<BM>>>,
+    /// Reachable states of the model alone, every partition's forward reachability stays
+    /// inside them.
+    model_reach: BM::Bdd,
 }
 
-impl PPSMC {
+impl<BM: BddManager> PPSMC<BM>
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
     pub fn new(
-        manager: BddManager,
-        fsmbdd: FsmBdd<BddManager>,
-        automata: BuchiAutomata,
+        manager: BM,
+        fsmbdd: FsmBdd<BM>,
+        automata: BuchiAutomata<BM>,
         args: Args,
+        model_reach: BM::Bdd,
     ) -> Self {
         let workers = Worker::create_workers(&fsmbdd, &automata)
             .into_iter()
@@ -42,7 +73,38 @@ impl PPSMC {
             args,
             workers,
             statistic: Statistic::default(),
+            model_reach,
+        }
+    }
+
+    /// The engine of the negated spec `ltl` from `get_ltl`, its propositions become defines of
+    /// `smv_bdd`.
+    pub fn from_ltl(
+        manager: &BM,
+        fsmbdd: &FsmBdd<BM>,
+        smv_bdd: &mut SmvBdd<BM>,
+        ltl: &Expr,
+        args: &Args,
+        model_reach: &BM::Bdd,
+    ) -> Self {
+        let ltl = smv_bdd.define_propositional(ltl);
+        let mut ba = BuchiAutomata::from_ltl(ltl, manager, &smv_bdd.symbols, &smv_bdd.defines)
+            .simplify(&fsmbdd.invariants);
+        dbg!(ba.num_state());
+        for var in args.ltl_extend_vars.iter() {
+            ba = ba.partition(*var);
+        }
+        if args.auto_partition {
+            ba = lace_run(|_| partition::auto_partition(fsmbdd, ba, args.parallel, args.verbose));
         }
+        dbg!(ba.num_state());
+        Self::new(
+            manager.clone(),
+            fsmbdd.clone(),
+            ba,
+            args.clone(),
+            model_reach.clone(),
+        )
     }
 
     pub fn check(&mut self) -> bool {
@@ -51,17 +113,33 @@ impl PPSMC {
             reach[*init_state] |= &self.fsmbdd.init;
         }
         let start = Instant::now();
-        reach = if self.args.old_impl {
+        reach = if self.args.on_the_fly {
+            match lace_run(|_| self.on_the_fly_post_reachable(&reach)) {
+                Some(reach) => reach,
+                None => {
+                    self.statistic.post_reachable_time += start.elapsed();
+                    return false;
+                }
+            }
+        } else if self.args.old_impl {
             lace_run(|context| self.lace_post_reachable(context, &reach))
         } else {
             async_block_on(self.new_parallel_post_reachable(&reach))
         };
         self.statistic.post_reachable_time += start.elapsed();
         let start = Instant::now();
-        let fair_states = if self.args.old_impl {
-            lace_run(|context| self.fair_states(context, &reach))
+        let (constraint, weak_fair) = lace_run(|_| self.fair_constraint(&reach));
+        if weak_fair {
+            self.statistic.fair_cycle_time += start.elapsed();
+            return false;
+        }
+        let fair_states = if self.args.fair_algorithm != FairAlgorithm::EmersonLei {
+            let algorithm = self.args.fair_algorithm.into();
+            lace_run(|_| fsmbdd::fair_cycle(&*self, &constraint, algorithm))
+        } else if self.args.old_impl {
+            lace_run(|context| self.fair_states(context, &constraint))
         } else {
-            async_block_on(self.async_fair_states(&reach))
+            async_block_on(self.async_fair_states(&constraint))
         };
         self.statistic.fair_cycle_time += start.elapsed();
         for accept in self.automata.accepting_states.iter() {
@@ -73,14 +151,10 @@ impl PPSMC {
     }
 }
 
-pub fn get_ltl(smv: &Smv, extend_trans: &[usize], flatten: bool) -> Expr {
+/// The negation of LTL spec `spec` under the fairness and the extended trans.
+pub fn get_ltl(smv: &Smv, spec: usize, extend_trans: &[usize]) -> Expr {
     dbg!(&smv.trans.len());
     dbg!(extend_trans);
-    let smv = if flatten {
-        smv.flatten_defines()
-    } else {
-        smv.clone()
-    };
     for x in extend_trans.iter() {
         dbg!(&smv.trans[*x]);
     }
@@ -97,41 +171,53 @@ pub fn get_ltl(smv: &Smv, extend_trans: &[usize], flatten: bool) -> Expr {
         );
         fairness = fairness & fair;
     }
-    let ltl = smv.ltlspecs[0].clone();
+    let ltl = smv.ltlspecs[spec].clone();
     let ltl = !Expr::InfixExpr(
         smv::Infix::Imply,
         Box::new(trans_ltl & fairness),
         Box::new(ltl),
     );
-    let ltl = ltl_to_automata_preprocess(&smv, ltl);
+    let ltl = ltl_to_automata_preprocess(smv, ltl);
     println!("{}", ltl);
     ltl
 }
 
-pub fn check(manager: BddManager, smv: Smv, args: Args) -> (bool, Duration) {
+/// Starts the workers of the async engine, once before the first `PPSMC::check`.
+pub fn create_async_workers(args: &Args) {
     if !args.old_impl {
         AsyncWorker::create(args.parallel);
     }
-    let smv_bdd = SmvBdd::new(&manager, &smv);
-    let mut fsmbdd = smv_bdd.to_fsmbdd(args.trans_method.into());
+}
+
+/// Checks every LTL spec of `smv`. With several specs the reachable states of the model are
+/// computed once and bound the forward reachability of each product.
+pub fn check<BM: BddManager>(manager: BM, smv: Smv, args: Args) -> (bool, Duration)
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    create_async_workers(&args);
+    let (mut smv_bdd, mut fsmbdd) = model_fsmbdd(&manager, &smv, &args);
+    dbg!(manager.num_var());
+    let model_reach = shared_model_reach(&smv, &fsmbdd, &args);
     fsmbdd.justice.clear();
-    dbg!(Sylvan::num_var());
-    let mut ba = BuchiAutomata::from_ltl(
-        get_ltl(&smv, &args.ltl_extend_trans, args.flatten_define),
-        &manager,
-        &smv_bdd.symbols,
-        &smv_bdd.defines,
-    );
-    dbg!(ba.num_state());
-    for var in args.ltl_extend_vars.iter() {
-        ba = ba.partition(*var);
+    let mut res = true;
+    let mut time = Duration::ZERO;
+    for spec in 0..smv.ltlspecs.len() {
+        let ltl = get_ltl(&smv, spec, &args.ltl_extend_trans);
+        let mut ppsmc = PPSMC::from_ltl(&manager, &fsmbdd, &mut smv_bdd, &ltl, &args, &model_reach);
+        dbg!("property-driven smc start checking");
+        let start = Instant::now();
+        let spec_res = ppsmc.check();
+        time += start.elapsed();
+        dbg!(ppsmc.statistic);
+        println!("ltlspec {}: {}", spec, spec_res);
+        res &= spec_res;
     }
-    dbg!(ba.num_state());
-    let mut ppsmc = PPSMC::new(manager, fsmbdd, ba, args);
-    dbg!("property-driven smc start checking");
-    let start = Instant::now();
-    let res = ppsmc.check();
-    let time = start.elapsed();
-    dbg!(ppsmc.statistic);
     (res, time)
 }
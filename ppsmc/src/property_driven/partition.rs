@@ -0,0 +1,174 @@
+use crate::automata::BuchiAutomata;
+use bdds::{Bdd, BddManager};
+use fsmbdd::FsmBdd;
+use std::{
+    collections::HashMap,
+    ops::{BitAnd, BitOr, BitXor, Not},
+};
+
+const SAMPLE_STEPS: usize = 8;
+const MAX_SPLITS: usize = 4;
+const NUM_CUBE_CANDIDATES: usize = 4;
+
+fn split_score<BM: BddManager>(sample: &[BM::Bdd], split: &BM::Bdd) -> Option<usize>
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    let mut score = 0;
+    for cell in sample.iter() {
+        let high = cell & split;
+        let low = cell & !split;
+        if high.is_constant(false) || low.is_constant(false) {
+            return None;
+        }
+        score = score.max(high.size()).max(low.size());
+    }
+    Some(score)
+}
+
+/// Splits the automaton until there are at least as many automaton states as `parallel`
+/// workers. The splits are chosen on the post-images of the first reachability iterations,
+/// which is what the workers of the split automaton states hold, a split (a variable or a cube
+/// of two literals) is better if the largest part of the sampled post-images is smaller.
+pub fn auto_partition<BM: BddManager>(
+    fsmbdd: &FsmBdd<BM>,
+    mut ba: BuchiAutomata<BM>,
+    parallel: usize,
+    verbose: bool,
+) -> BuchiAutomata<BM>
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    let manager = &fsmbdd.manager;
+    let mut reach = fsmbdd.init.clone();
+    let mut post = manager.constant(false);
+    let mut frontier = reach.clone();
+    for _ in 0..SAMPLE_STEPS {
+        let image = fsmbdd.post_image(&frontier);
+        post |= &image;
+        frontier = image & !&reach;
+        if frontier.is_constant(false) {
+            break;
+        }
+        reach |= &frontier;
+    }
+    let names: HashMap<usize, &String> = fsmbdd
+        .symbols
+        .iter()
+        .map(|(name, var)| (*var, name))
+        .collect();
+    let mut sample = vec![post];
+    let mut num_split = 0;
+    while ba.num_state() < parallel && num_split < MAX_SPLITS {
+        let mut scored: Vec<(usize, usize)> = names
+            .keys()
+            .filter_map(|var| {
+                split_score::<BM>(&sample, &manager.ith_var(*var)).map(|score| (score, *var))
+            })
+            .collect();
+        scored.sort();
+        let mut candidates: Vec<(usize, BM::Bdd, String)> = scored
+            .iter()
+            .map(|(score, var)| (*score, manager.ith_var(*var), names[var].to_string()))
+            .collect();
+        let top: Vec<usize> = scored
+            .iter()
+            .take(NUM_CUBE_CANDIDATES)
+            .map(|(_, var)| *var)
+            .collect();
+        for (i, x) in top.iter().enumerate() {
+            for y in top[i + 1..].iter() {
+                for (x_val, y_val) in [(true, true), (true, false), (false, true), (false, false)] {
+                    let lit = |var: usize, val: bool| {
+                        let bdd = manager.ith_var(var);
+                        if val {
+                            bdd
+                        } else {
+                            !bdd
+                        }
+                    };
+                    let cube = lit(*x, x_val) & lit(*y, y_val);
+                    if let Some(score) = split_score::<BM>(&sample, &cube) {
+                        let name = format!(
+                            "{}{} & {}{}",
+                            if x_val { "" } else { "!" },
+                            names[x],
+                            if y_val { "" } else { "!" },
+                            names[y]
+                        );
+                        candidates.push((score, cube, name));
+                    }
+                }
+            }
+        }
+        let Some((score, split, name)) = candidates.into_iter().min_by_key(|(score, _, _)| *score)
+        else {
+            break;
+        };
+        if verbose {
+            println!("auto partition: split on {} (score {})", name, score);
+        }
+        sample = sample
+            .iter()
+            .flat_map(|cell| [cell & &split, cell & !&split])
+            .collect();
+        ba = ba.partition_by(&split);
+        num_split += 1;
+    }
+    ba
+}
+
+#[cfg(test)]
+mod tests {
+    use super::auto_partition;
+    use crate::{automata::BuchiAutomata, BddManager as Manager};
+    use bdds::BddManager;
+    use fsmbdd::{TransBddMethod, DEFAULT_CLUSTER_THRESHOLD};
+    use smv::{bdd::SmvBdd, Smv};
+
+    /// After the first step `x` separates two parities of equal size, every other variable
+    /// leaves one side whole in both parts.
+    const PARITIES: &str = "MODULE main
+VAR
+x : boolean;
+y0 : boolean;
+y1 : boolean;
+y2 : boolean;
+z0 : boolean;
+z1 : boolean;
+z2 : boolean;
+INIT
+!x & !y0 & !y1 & !y2 & !z0 & !z1 & !z2
+TRANS
+next(x) -> (next(y0) xor next(y1) xor next(y2))
+TRANS
+!next(x) -> (next(z0) xor next(z1) xor next(z2))
+";
+
+    #[test]
+    fn test_balanced_split() {
+        let manager = <Manager as BddManager>::new();
+        let smv = Smv::parse(PARITIES);
+        let smvbdd = SmvBdd::new(&manager, &smv);
+        let fsmbdd = smvbdd.to_fsmbdd(TransBddMethod::Partition, DEFAULT_CLUSTER_THRESHOLD);
+        let mut ba = BuchiAutomata::new(manager.clone());
+        ba.add_edge(0, 0, manager.constant(true));
+        ba.add_init_state(0);
+        let ba = auto_partition(&fsmbdd, ba, 2, false);
+        assert_eq!(ba.num_state(), 2);
+        let x = manager.ith_var(smvbdd.symbols["x"]);
+        assert!(ba.forward[0].contains(&(1, x)));
+    }
+}
@@ -1,15 +1,28 @@
 use super::PPSMC;
-use crate::Bdd;
+use crate::lace::LaceWorkerContext;
 use arun::async_spawn;
-use std::{sync::Arc, time::Instant};
-use sylvan::LaceWorkerContext;
+use bdds::{Bdd, BddManager};
+use std::{
+    ops::{BitAnd, BitOr, BitXor, Not},
+    sync::Arc,
+    time::Instant,
+};
 
-impl PPSMC {
+impl<BM: BddManager> PPSMC<BM>
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
     pub fn lace_post_reachable(
         &mut self,
         mut context: LaceWorkerContext,
-        from: &[Bdd],
-    ) -> Vec<Bdd> {
+        from: &[BM::Bdd],
+    ) -> Vec<BM::Bdd> {
         let mut frontier = from.to_vec();
         let partitioned_len = from.len();
         let mut reach = frontier.clone();
@@ -35,22 +48,27 @@ impl PPSMC {
             self.statistic.post_propagate_time += start.elapsed();
             let start = Instant::now();
             for i in 0..partitioned_len {
-                let bdd = tmp[i].clone();
-                let mut reach = reach[i].clone();
-                let fsmbdd = self.workers[i].fsmbdd.clone();
+                let bdd = self.manager.detach(&tmp[i]);
+                let reach = self.manager.detach(&reach[i]);
+                let model_reach = self.manager.detach(&self.model_reach);
+                let worker = self.workers[i].clone();
                 context.lace_spawn(move |_| {
-                    let image = fsmbdd.post_image(&bdd);
+                    let manager = &worker.fsmbdd.manager;
+                    let image = worker.fsmbdd.post_image(&manager.attach(&bdd))
+                        & manager.attach(&model_reach);
+                    let mut reach = manager.attach(&reach);
                     reach |= &image;
-                    (reach, image)
+                    (manager.detach(&reach), manager.detach(&image))
                 });
             }
-            let reach_update: Vec<(Bdd, Bdd)> = context.lace_sync_multi(partitioned_len);
+            let reach_update: Vec<(BM::Detached, BM::Detached)> =
+                context.lace_sync_multi(partitioned_len);
             self.statistic.post_image_time += start.elapsed();
             frontier.clear();
             reach = Vec::new();
             for (reach_bdd, update) in reach_update {
-                reach.push(reach_bdd);
-                frontier.push(update);
+                reach.push(self.manager.attach(&reach_bdd));
+                frontier.push(self.manager.attach(&update));
             }
         }
     }
@@ -58,31 +76,37 @@ impl PPSMC {
     fn lace_pre_iteration(
         &mut self,
         mut context: LaceWorkerContext,
-        states: Vec<Bdd>,
-        reach: &[Bdd],
-        constraint: &[Bdd],
-    ) -> (Vec<Bdd>, Vec<Bdd>) {
+        states: Vec<BM::Bdd>,
+        reach: &[BM::Bdd],
+        constraint: &[BM::Bdd],
+    ) -> (Vec<BM::Bdd>, Vec<BM::Bdd>) {
         let partitioned_len = states.len();
-        let states = Arc::new(states);
+        let states: Arc<Vec<BM::Detached>> =
+            Arc::new(states.iter().map(|s| self.manager.detach(s)).collect());
         for i in 0..partitioned_len {
             let worker = self.workers[i].clone();
-            let reach = reach[i].clone();
+            let reach = self.manager.detach(&reach[i]);
             let states = states.clone();
-            let constraint = constraint[i].clone();
+            let constraint = self.manager.detach(&constraint[i]);
             context.lace_spawn(move |_| {
-                let (reach, mut new_frontier) = worker.propagate_value(reach, states, constraint);
+                let manager = &worker.fsmbdd.manager;
+                let (reach, mut new_frontier) = worker.propagate_value(
+                    manager.attach(&reach),
+                    &states,
+                    manager.attach(&constraint),
+                );
                 if !new_frontier.is_constant(false) {
                     new_frontier = worker.fsmbdd.pre_image(&new_frontier);
                 }
-                (reach, new_frontier)
+                (manager.detach(&reach), manager.detach(&new_frontier))
             })
         }
-        let res = context.lace_sync_multi::<(Bdd, Bdd)>(partitioned_len);
+        let res = context.lace_sync_multi::<(BM::Detached, BM::Detached)>(partitioned_len);
         let mut reach = Vec::new();
         let mut new_frontier = Vec::new();
         for (r, f) in res.into_iter() {
-            reach.push(r);
-            new_frontier.push(f);
+            reach.push(self.manager.attach(&r));
+            new_frontier.push(self.manager.attach(&f));
         }
         (reach, new_frontier)
     }
@@ -90,19 +114,26 @@ impl PPSMC {
     pub fn lace_pre_reachable(
         &mut self,
         mut context: LaceWorkerContext,
-        from: &[Bdd],
-        constraint: &[Bdd],
-    ) -> Vec<Bdd> {
+        from: &[BM::Bdd],
+        constraint: &[BM::Bdd],
+    ) -> Vec<BM::Bdd> {
         let partitioned_len = from.len();
         let mut frontier = from.to_vec();
         let mut reach = vec![self.manager.constant(false); partitioned_len];
         let mut y = 0;
         for i in 0..partitioned_len {
-            let fsmbdd = self.workers[i].fsmbdd.clone();
-            let x = frontier[i].clone();
-            context.lace_spawn(move |_| fsmbdd.pre_image(&x));
+            let worker = self.workers[i].clone();
+            let x = self.manager.detach(&frontier[i]);
+            context.lace_spawn(move |_| {
+                let manager = &worker.fsmbdd.manager;
+                manager.detach(&worker.fsmbdd.pre_image(&manager.attach(&x)))
+            });
         }
-        frontier = context.lace_sync_multi(partitioned_len);
+        frontier = context
+            .lace_sync_multi::<BM::Detached>(partitioned_len)
+            .iter()
+            .map(|x| self.manager.attach(x))
+            .collect();
         loop {
             y += 1;
             if self.args.verbose {
@@ -121,38 +152,47 @@ impl PPSMC {
     }
 }
 
-impl PPSMC {
-    pub async fn new_parallel_post_reachable(&mut self, from: &[Bdd]) -> Vec<Bdd> {
-        let constraint = vec![self.manager.constant(true); from.len()];
+impl<BM: BddManager> PPSMC<BM>
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    pub async fn new_parallel_post_reachable(&mut self, from: &[BM::Bdd]) -> Vec<BM::Bdd> {
+        let constraint = vec![self.model_reach.clone(); from.len()];
         self.new_parallel_reachable(from, &constraint, true).await
     }
 
     pub async fn new_parallel_pre_reachable(
         &mut self,
-        from: &[Bdd],
-        constraint: &[Bdd],
-    ) -> Vec<Bdd> {
+        from: &[BM::Bdd],
+        constraint: &[BM::Bdd],
+    ) -> Vec<BM::Bdd> {
         self.new_parallel_reachable(from, constraint, false).await
     }
 
     async fn new_parallel_reachable(
         &mut self,
-        from: &[Bdd],
-        constraint: &[Bdd],
+        from: &[BM::Bdd],
+        constraint: &[BM::Bdd],
         forward: bool,
-    ) -> Vec<Bdd> {
+    ) -> Vec<BM::Bdd> {
         for worker in self.workers.iter_mut() {
             Arc::get_mut(worker).unwrap().reset().await
         }
         let mut joins = Vec::new();
         for i in 0..self.workers.len() {
-            let init = from[i].clone();
-            let constraint = constraint[i].clone();
+            let init = self.manager.detach(&from[i]);
+            let constraint = self.manager.detach(&constraint[i]);
             let mut worker = self.workers[i].clone();
             joins.push(async_spawn(async move {
                 if forward {
                     unsafe { Arc::get_mut_unchecked(&mut worker) }
-                        .post_reachable(init)
+                        .post_reachable(init, constraint)
                         .await
                 } else {
                     unsafe { Arc::get_mut_unchecked(&mut worker) }
@@ -163,7 +203,7 @@ impl PPSMC {
         }
         let mut res = Vec::new();
         for join in joins {
-            res.push(join.await);
+            res.push(self.manager.attach(&join.await));
         }
         res
     }
@@ -0,0 +1,246 @@
+use super::{create_async_workers, get_ltl, PPSMC};
+use crate::{
+    command::Args,
+    lace::lace_run,
+    traditional::{fair_sanity, model_fsmbdd},
+};
+use bdds::{Bdd, BddManager};
+use fsmbdd::FsmBdd;
+use smv::{bdd::SmvBdd, CaseExpr, Expr, Infix, Prefix, Smv};
+use std::{
+    ops::{BitAnd, BitOr, BitXor, Not},
+    time::{Duration, Instant},
+};
+
+/// Names and polarities of the atom occurrences of `expr` in preorder. The polarity is `None`
+/// under iff, xor and case conditions, where the atom appears both ways.
+fn atoms(expr: &Expr, polarity: Option<bool>, res: &mut Vec<(String, Option<bool>)>) {
+    match expr {
+        Expr::Ident(ident) => res.push((ident.clone(), polarity)),
+        Expr::LitExpr(_) => (),
+        Expr::PrefixExpr(Prefix::Not, sub_expr) => atoms(sub_expr, polarity.map(|p| !p), res),
+        Expr::PrefixExpr(_, sub_expr) => atoms(sub_expr, polarity, res),
+        Expr::InfixExpr(Infix::Imply, left, right) => {
+            atoms(left, polarity.map(|p| !p), res);
+            atoms(right, polarity, res);
+        }
+        Expr::InfixExpr(Infix::Iff | Infix::Xor, left, right) => {
+            atoms(left, None, res);
+            atoms(right, None, res);
+        }
+        Expr::InfixExpr(_, left, right) => {
+            atoms(left, polarity, res);
+            atoms(right, polarity, res);
+        }
+        Expr::CaseExpr(case_expr) => {
+            for (cond, branch) in case_expr.branchs.iter() {
+                atoms(cond, None, res);
+                atoms(branch, polarity, res);
+            }
+        }
+    }
+}
+
+/// `expr` with its atom occurrence `target`, in the order of `atoms`, replaced by `value`.
+fn replace_atom(expr: &Expr, target: usize, value: bool, count: &mut usize) -> Expr {
+    match expr {
+        Expr::Ident(_) => {
+            *count += 1;
+            if *count - 1 == target {
+                Expr::LitExpr(value)
+            } else {
+                expr.clone()
+            }
+        }
+        Expr::LitExpr(_) => expr.clone(),
+        Expr::PrefixExpr(op, sub_expr) => Expr::PrefixExpr(
+            op.clone(),
+            Box::new(replace_atom(sub_expr, target, value, count)),
+        ),
+        Expr::InfixExpr(op, left, right) => {
+            let left = replace_atom(left, target, value, count);
+            let right = replace_atom(right, target, value, count);
+            Expr::InfixExpr(op.clone(), Box::new(left), Box::new(right))
+        }
+        Expr::CaseExpr(case_expr) => Expr::CaseExpr(CaseExpr {
+            branchs: case_expr
+                .branchs
+                .iter()
+                .map(|(cond, branch)| {
+                    let cond = replace_atom(cond, target, value, count);
+                    (cond, replace_atom(branch, target, value, count))
+                })
+                .collect(),
+        }),
+    }
+}
+
+/// Propositional antecedents of the implications of `expr`.
+fn antecedents(expr: &Expr, res: &mut Vec<Expr>) {
+    match expr {
+        Expr::InfixExpr(op, left, right) => {
+            if *op == Infix::Imply && left.is_propositional() {
+                res.push(*left.clone());
+            }
+            antecedents(left, res);
+            antecedents(right, res);
+        }
+        Expr::PrefixExpr(_, sub_expr) => antecedents(sub_expr, res),
+        Expr::CaseExpr(case_expr) => {
+            for (cond, branch) in case_expr.branchs.iter() {
+                antecedents(cond, res);
+                antecedents(branch, res);
+            }
+        }
+        Expr::Ident(_) | Expr::LitExpr(_) => (),
+    }
+}
+
+fn engine<BM: BddManager>(
+    fsmbdd: &FsmBdd<BM>,
+    smv_bdd: &mut SmvBdd<BM>,
+    smv: &Smv,
+    spec: usize,
+    args: &Args,
+    model_reach: &BM::Bdd,
+) -> PPSMC<BM>
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    let ltl = get_ltl(smv, spec, &args.ltl_extend_trans);
+    let manager = fsmbdd.manager.clone();
+    PPSMC::from_ltl(&manager, fsmbdd, smv_bdd, &ltl, args, model_reach)
+}
+
+/// Prints the model states of a lasso, after the first state only the variables that change.
+fn print_lasso<BM: BddManager>(fsmbdd: &FsmBdd<BM>, stem: &[Vec<BM::Bdd>], cycle: &[Vec<BM::Bdd>])
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    let mut symbols: Vec<(&String, &usize)> = fsmbdd.symbols.iter().collect();
+    symbols.sort_by_key(|(_, var)| **var);
+    let mut last: Vec<Option<bool>> = vec![None; symbols.len()];
+    for (i, state) in stem.iter().chain(cycle.iter()).enumerate() {
+        if i == stem.len() {
+            println!("-- Loop starts here");
+        }
+        println!("-> State: {} <-", i + 1);
+        let state = state
+            .iter()
+            .fold(fsmbdd.manager.constant(false), |res, bdd| res | bdd);
+        for (j, (ident, var)) in symbols.iter().enumerate() {
+            let value = !(&state & fsmbdd.manager.ith_var(**var)).is_constant(false);
+            if last[j] != Some(value) {
+                println!("  {} = {}", ident, Expr::LitExpr(value));
+                last[j] = Some(value);
+            }
+        }
+    }
+}
+
+/// Checks every LTL spec with the property-driven engine, then each passing spec once more for
+/// every atom occurrence replaced by the constant that makes the spec stronger, both constants
+/// if the occurrence has no polarity. A spec that still holds is vacuous in that occurrence.
+/// A non-vacuous pass gets an interesting witness, a counterexample of its first mutation,
+/// and implications whose antecedent holds on no reachable state are reported.
+pub fn check<BM: BddManager>(manager: BM, smv: Smv, args: Args) -> (bool, Duration)
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    create_async_workers(&args);
+    let (mut smv_bdd, mut fsmbdd) = model_fsmbdd(&manager, &smv, &args);
+    let start = Instant::now();
+    let model_reach = lace_run(|_| fsmbdd.reachable_from_init());
+    fair_sanity(&fsmbdd, &model_reach, &args);
+    fsmbdd.justice.clear();
+    let mut res = true;
+    for spec in 0..smv.ltlspecs.len() {
+        let mut ppsmc = engine(&fsmbdd, &mut smv_bdd, &smv, spec, &args, &model_reach);
+        if !ppsmc.check() {
+            println!("ltlspec {}: false", spec);
+            res = false;
+            continue;
+        }
+        let mut implications = Vec::new();
+        antecedents(
+            &smv.flatten_to_propositional_define(&smv.ltlspecs[spec]),
+            &mut implications,
+        );
+        for antecedent in implications {
+            if (smv_bdd.expr_to_bdd(&antecedent) & &model_reach).is_constant(false) {
+                println!(
+                    "ltlspec {}: antecedent `{}` holds on no reachable state",
+                    spec, antecedent
+                );
+            }
+        }
+        let mut occurrences = Vec::new();
+        atoms(&smv.ltlspecs[spec], Some(true), &mut occurrences);
+        let mut vacuous = false;
+        let mut witness = None;
+        for (i, (atom, polarity)) in occurrences.iter().enumerate() {
+            let values = match polarity {
+                Some(polarity) => vec![!polarity],
+                None => vec![false, true],
+            };
+            let mut holds = true;
+            for value in values.iter() {
+                let mut mutant = smv.clone();
+                mutant.ltlspecs[spec] = replace_atom(&smv.ltlspecs[spec], i, *value, &mut 0);
+                let mut ppsmc = engine(&fsmbdd, &mut smv_bdd, &mutant, spec, &args, &model_reach);
+                if !ppsmc.check() {
+                    holds = false;
+                    if witness.is_none() {
+                        witness = lace_run(|_| ppsmc.witness()).map(|lasso| (atom.clone(), lasso));
+                    }
+                    break;
+                }
+            }
+            if holds {
+                vacuous = true;
+                let values: Vec<String> = values
+                    .iter()
+                    .map(|v| Expr::LitExpr(*v).to_string())
+                    .collect();
+                println!(
+                    "ltlspec {}: vacuous in occurrence {} of `{}`, still holds with {}",
+                    spec,
+                    i,
+                    atom,
+                    values.join(" and ")
+                );
+            }
+        }
+        if vacuous {
+            println!("ltlspec {}: true, vacuously", spec);
+            continue;
+        }
+        println!("ltlspec {}: true", spec);
+        if let Some((atom, (stem, cycle))) = witness {
+            println!(
+                "ltlspec {}: interesting witness, `{}` matters on it",
+                spec, atom
+            );
+            print_lasso(&fsmbdd, &stem, &cycle);
+        }
+    }
+    (res, start.elapsed())
+}
@@ -1,40 +1,73 @@
-use crate::{automata::BuchiAutomata, Bdd, BddManager};
+use crate::automata::BuchiAutomata;
+use bdds::{Bdd, BddManager};
 use fsmbdd::FsmBdd;
-use std::sync::{
-    atomic::{AtomicI32, Ordering},
-    Arc,
+use std::{
+    ops::{BitAnd, BitOr, BitXor, Not},
+    sync::{
+        atomic::{AtomicI32, Ordering},
+        Arc,
+    },
 };
 use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
 
 #[derive(Debug)]
-enum Message {
-    Data(Bdd),
+enum Message<D> {
+    Data(D),
     Quit,
 }
 
-pub struct Worker {
+pub struct Worker<BM: BddManager>
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
     id: usize,
-    manager: BddManager,
-    pub fsmbdd: FsmBdd<BddManager>,
-    sender: Vec<UnboundedSender<Message>>,
-    receiver: UnboundedReceiver<Message>,
+    manager: BM,
+    pub fsmbdd: FsmBdd<BM>,
+    sender: Vec<UnboundedSender<Message<BM::Detached>>>,
+    receiver: UnboundedReceiver<Message<BM::Detached>>,
     active: Arc<AtomicI32>,
-    forward: Vec<(usize, Bdd)>,
-    backward: Vec<(usize, Bdd)>,
+    forward: Vec<(usize, BM::Bdd)>,
+    backward: Vec<(usize, BM::Bdd)>,
+    // label of the automaton self loop, worker saturates along it locally
+    self_loop: Option<BM::Bdd>,
 }
 
-unsafe impl Sync for Worker {}
+unsafe impl<BM: BddManager> Sync for Worker<BM> where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>
+{
+}
 
-impl Worker {
+impl<BM: BddManager> Worker<BM>
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
     pub fn propagate_value(
         &self,
-        mut reach: Bdd,
-        data: Arc<Vec<Bdd>>,
-        constraint: Bdd,
-    ) -> (Bdd, Bdd) {
+        mut reach: BM::Bdd,
+        data: &[BM::Detached],
+        constraint: BM::Bdd,
+    ) -> (BM::Bdd, BM::Bdd) {
         let mut new_frontier = self.manager.constant(false);
         for (from, label) in self.forward.iter() {
-            let mut update = &data[*from] & label & &constraint;
+            let mut update = self.manager.attach(&data[*from]) & label & &constraint;
             update &= !&reach;
             new_frontier |= &update;
             reach |= update;
@@ -42,7 +75,7 @@ impl Worker {
         (reach, new_frontier)
     }
 
-    async fn propagate(&mut self, forward: bool, data: Bdd) {
+    async fn propagate(&mut self, forward: bool, data: BM::Bdd) {
         if data.is_constant(false) {
             return;
         }
@@ -55,6 +88,7 @@ impl Worker {
             let message = &data & label;
             if !message.is_constant(false) {
                 self.active.fetch_add(1, Ordering::Relaxed);
+                let message = self.manager.detach(&message);
                 self.sender[*next].send(Message::Data(message)).unwrap();
             }
         }
@@ -78,33 +112,44 @@ impl Worker {
         self.active.fetch_max(self.id as i32 + 1, Ordering::Relaxed);
     }
 
-    pub async fn post_reachable(&mut self, init: Bdd) -> Bdd {
+    pub async fn post_reachable(
+        &mut self,
+        init: BM::Detached,
+        constraint: BM::Detached,
+    ) -> BM::Detached {
+        let init = self.manager.attach(&init);
+        let constraint = self.manager.attach(&constraint);
         let mut reach = init.clone();
         self.propagate(true, init).await;
         loop {
             if self.active.fetch_sub(1, Ordering::Relaxed) == 1 {
                 self.quit();
-                return reach;
+                return self.manager.detach(&reach);
             }
             let mut update = self.manager.constant(false);
             match self.receiver.recv().await.unwrap() {
                 Message::Data(data) => {
-                    update |= &data;
+                    update |= self.manager.attach(&data);
                 }
-                Message::Quit => return reach,
+                Message::Quit => return self.manager.detach(&reach),
             }
             let mut num_update: i32 = 0;
             while let Ok(message) = self.receiver.try_recv() {
                 match message {
                     Message::Data(data) => {
-                        update |= &data;
+                        update |= self.manager.attach(&data);
                         num_update -= 1;
                     }
                     _ => panic!(),
                 }
             }
             if !update.is_constant(false) {
-                let mut update = self.fsmbdd.post_image(&update);
+                let mut update = self.fsmbdd.post_image(&update) & &constraint;
+                if let Some(self_loop) = &self.self_loop {
+                    update =
+                        self.fsmbdd
+                            .reachable_with_guard(&update, true, self_loop, &constraint);
+                }
                 update &= !&reach;
                 reach |= &update;
                 self.propagate(true, update).await;
@@ -113,7 +158,13 @@ impl Worker {
         }
     }
 
-    pub async fn pre_reachable(&mut self, init: Bdd, constraint: Bdd) -> Bdd {
+    pub async fn pre_reachable(
+        &mut self,
+        init: BM::Detached,
+        constraint: BM::Detached,
+    ) -> BM::Detached {
+        let init = self.manager.attach(&init);
+        let constraint = self.manager.attach(&constraint);
         let mut reach = self.manager.constant(false);
         if init != self.manager.constant(false) {
             self.propagate(false, self.fsmbdd.pre_image(&init)).await;
@@ -121,26 +172,34 @@ impl Worker {
         loop {
             if self.active.fetch_sub(1, Ordering::Relaxed) == 1 {
                 self.quit();
-                return reach & init;
+                return self.manager.detach(&(reach & init));
             }
             let mut update = self.manager.constant(false);
             match self.receiver.recv().await.unwrap() {
                 Message::Data(data) => {
-                    update |= &data;
+                    update |= self.manager.attach(&data);
                 }
-                Message::Quit => return reach & init,
+                Message::Quit => return self.manager.detach(&(reach & init)),
             }
             let mut num_update: i32 = 0;
             while let Ok(message) = self.receiver.try_recv() {
                 match message {
                     Message::Data(data) => {
-                        update |= &data;
+                        update |= self.manager.attach(&data);
                         num_update -= 1;
                     }
                     _ => panic!(),
                 }
             }
             update &= &constraint;
+            if let Some(self_loop) = &self.self_loop {
+                let guard = self.manager.constant(true);
+                let constrain = self_loop & &constraint;
+                let pre = self.fsmbdd.pre_image(&update) & &constrain;
+                update |= self
+                    .fsmbdd
+                    .reachable_with_guard(&pre, false, &guard, &constrain);
+            }
             update &= !&reach;
             reach |= &update;
             if !update.is_constant(false) {
@@ -151,7 +210,9 @@ impl Worker {
         }
     }
 
-    pub fn create_workers(fsmbdd: &FsmBdd<BddManager>, automata: &BuchiAutomata) -> Vec<Self> {
+    /// One worker per automaton state, each on a manager of its own. With a package of a single
+    /// node table (`BddManager::SHARED_TABLE`) the workers share that table.
+    pub fn create_workers(fsmbdd: &FsmBdd<BM>, automata: &BuchiAutomata<BM>) -> Vec<Self> {
         let mut recievers = vec![];
         let mut senders = vec![];
         let mut workers = vec![];
@@ -163,8 +224,21 @@ impl Worker {
         }
         for (id, receiver) in recievers.into_iter().enumerate() {
             let fsmbdd = fsmbdd.clone_with_new_manager();
-            let forward = automata.forward[id].clone();
-            let backward = automata.backward[id].clone();
+            let translocate = |trans: &[(usize, BM::Bdd)]| {
+                trans
+                    .iter()
+                    .map(|(state, label)| (*state, fsmbdd.manager.translocate(label)))
+                    .collect()
+            };
+            let forward: Vec<(usize, BM::Bdd)> = translocate(&automata.forward[id]);
+            let backward = translocate(&automata.backward[id]);
+            let self_loop = forward
+                .iter()
+                .filter(|(next, _)| *next == id)
+                .fold(None, |res: Option<BM::Bdd>, (_, label)| {
+                    Some(res.map_or_else(|| label.clone(), |res| res | label))
+                })
+                .filter(|_| fsmbdd.trans.is_saturation());
             workers.push(Self {
                 id,
                 manager: fsmbdd.manager.clone(),
@@ -174,8 +248,98 @@ impl Worker {
                 active: active.clone(),
                 forward,
                 backward,
+                self_loop,
             })
         }
         workers
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Worker;
+    use crate::{automata::BuchiAutomata, BddManager as Manager};
+    use bdds::BddManager;
+    use fsmbdd::{TransBddMethod, DEFAULT_CLUSTER_THRESHOLD};
+    use smv::{bdd::SmvBdd, Smv};
+    use tokio::runtime::Builder;
+
+    /// `x` toggles while `sel` is set, `sel` changes freely, so saturation splits on it.
+    const TOGGLE: &str = "MODULE main
+VAR
+sel : boolean;
+x : boolean;
+INIT
+!x & !sel
+TRANS
+next(x) <-> case sel : !x; TRUE : x; esac
+";
+
+    fn reachable(
+        manager: &Manager,
+        mut workers: Vec<Worker<Manager>>,
+        from: &[<Manager as BddManager>::Bdd],
+        forward: bool,
+    ) -> Vec<<Manager as BddManager>::Bdd> {
+        let runtime = Builder::new_current_thread().build().unwrap();
+        let constraint = manager.constant(true);
+        let res = runtime.block_on(async {
+            for worker in workers.iter_mut() {
+                worker.reset().await;
+            }
+            let mut joins = Vec::new();
+            for (mut worker, from) in workers.into_iter().zip(from.iter()) {
+                let from = manager.detach(from);
+                let constraint = manager.detach(&constraint);
+                joins.push(tokio::spawn(async move {
+                    if forward {
+                        worker.post_reachable(from, constraint).await
+                    } else {
+                        worker.pre_reachable(from, constraint).await
+                    }
+                }));
+            }
+            let mut res = Vec::new();
+            for join in joins {
+                res.push(join.await.unwrap());
+            }
+            res
+        });
+        res.iter().map(|bdd| manager.attach(bdd)).collect()
+    }
+
+    #[test]
+    fn test_self_loop_saturation() {
+        let manager = <Manager as BddManager>::new();
+        let smv = Smv::parse(TOGGLE);
+        let smvbdd = SmvBdd::new(&manager, &smv);
+        let fsmbdd = smvbdd.to_fsmbdd(TransBddMethod::Saturation, DEFAULT_CLUSTER_THRESHOLD);
+        assert!(fsmbdd.trans.is_saturation());
+        let x = manager.ith_var(smvbdd.symbols["x"]);
+        // waits in state 0 until `x` is set, then stays in state 1
+        let mut automata = BuchiAutomata::new(manager.clone());
+        automata.add_edge(0, 0, manager.constant(true));
+        automata.add_edge(0, 1, x.clone());
+        automata.add_edge(1, 1, manager.constant(true));
+        automata.add_init_state(0);
+        let workers = || Worker::create_workers(&fsmbdd, &automata);
+        let without_self_loop = || {
+            let mut workers = workers();
+            for worker in workers.iter_mut() {
+                worker.self_loop = None;
+            }
+            workers
+        };
+        assert!(workers().iter().all(|worker| worker.self_loop.is_some()));
+        let init = vec![fsmbdd.init.clone(), manager.constant(false)];
+        let post = reachable(&manager, workers(), &init, true);
+        assert_eq!(post, reachable(&manager, without_self_loop(), &init, true));
+        assert!(!(&post[1] & &x).is_constant(false));
+        let target = vec![manager.constant(false), x.clone()];
+        let pre = reachable(&manager, workers(), &target, false);
+        assert_eq!(
+            pre,
+            reachable(&manager, without_self_loop(), &target, false)
+        );
+    }
+}
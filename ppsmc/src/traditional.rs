@@ -1,27 +1,246 @@
 use crate::{
-    automata::BuchiAutomata, command::Args, ltl::ltl_to_automata_preprocess,
-    property_driven::get_ltl, BddManager,
+    automata::BuchiAutomata, command::Args, lace::lace_run, ltl::ltl_to_automata_preprocess,
+    property_driven::get_ltl,
 };
+use aiger::Aig;
+use bdds::{Bdd, BddManager};
+use fsmbdd::FsmBdd;
 use smv::{bdd::SmvBdd, Smv};
-use std::time::{Duration, Instant};
-use sylvan::lace_run;
+use std::{
+    ops::{BitAnd, BitOr, BitXor, Not},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// The model of `smv` and its `SmvBdd`, shared by the products of its specs.
+pub fn model_fsmbdd<BM: BddManager>(
+    manager: &BM,
+    smv: &Smv,
+    args: &Args,
+) -> (SmvBdd<BM>, FsmBdd<BM>)
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    let smvbdd = SmvBdd::new(manager, smv);
+    let mut fsmbdd = smvbdd.to_fsmbdd(args.trans_method.into(), args.cluster_threshold);
+    fsmbdd.trans.set_parallel(args.parallel_image);
+    if args.verbose {
+        println!(
+            "post image schedule (cluster, size, quantified): {:?}",
+            fsmbdd.trans.schedule(true)
+        );
+        println!(
+            "pre image schedule (cluster, size, quantified): {:?}",
+            fsmbdd.trans.schedule(false)
+        );
+    }
+    (smvbdd, fsmbdd)
+}
+
+/// Writes the model of `smv` to `--export` as `model_fsmbdd` encodes it: the initial states,
+/// the invariants, every trans partition and the justice constraints.
+pub fn export_model<BM: BddManager>(manager: &BM, smv: &Smv, args: &Args)
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    let fsmbdd =
+        SmvBdd::new(manager, smv).to_fsmbdd(args.trans_method.into(), args.cluster_threshold);
+    Aig::from_fsmbdd(&fsmbdd).write_file(&args.export).unwrap();
+}
+
+/// `file` with `index` appended to its stem, `product.aag` becomes `product3.aag`.
+fn indexed_file(file: &str, index: usize) -> PathBuf {
+    let path = Path::new(file);
+    let mut name = path.file_stem().unwrap_or_default().to_os_string();
+    name.push(index.to_string());
+    if let Some(extension) = path.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+    path.with_file_name(name)
+}
+
+/// Panics if no initial state satisfies the invariants or no reachable infinite path of the
+/// model visits every fairness constraint infinitely often, every spec would hold vacuously
+/// on such a model. Only warns with `--allow-unfair`.
+pub fn fair_sanity<BM: BddManager>(fsmbdd: &FsmBdd<BM>, reach: &BM::Bdd, args: &Args)
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    let problem = if reach.is_constant(false) {
+        "the model has no initial state satisfying its invariants"
+    } else {
+        let mut fsmbdd = fsmbdd.clone();
+        if fsmbdd.justice.is_empty() {
+            fsmbdd.justice.push(fsmbdd.manager.constant(true));
+        }
+        let fair = lace_run(|_| fsmbdd.fair_cycle_with_constrain(reach));
+        if !(fair & reach).is_constant(false) {
+            return;
+        }
+        "the model has no fair path, its fairness constraints or deadlocks cut every infinite path"
+    };
+    if args.allow_unfair {
+        println!("warning: {}, every spec holds vacuously", problem);
+    } else {
+        panic!("{}, every spec would hold vacuously", problem);
+    }
+}
+
+/// Reachable states of the model, checked by `fair_sanity`. They bound the products only
+/// when several specs are checked, otherwise true is returned.
+pub fn shared_model_reach<BM: BddManager>(smv: &Smv, fsmbdd: &FsmBdd<BM>, args: &Args) -> BM::Bdd
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    let start = Instant::now();
+    let reach = lace_run(|_| fsmbdd.reachable_from_init());
+    if args.verbose {
+        println!("model reachable states computed in {:?}", start.elapsed());
+    }
+    fair_sanity(fsmbdd, &reach, args);
+    if smv.ltlspecs.len() < 2 {
+        return fsmbdd.manager.constant(true);
+    }
+    reach
+}
 
-pub fn check(manager: BddManager, smv: Smv, args: Args) -> (bool, Duration) {
-    let smvbdd = SmvBdd::new(&manager, &smv);
-    let mut fsmbdd = smvbdd.to_fsmbdd(args.trans_method.into());
+/// Product of the model with the automaton of the negated LTL spec `spec`.
+pub fn ltl_product<BM: BddManager>(
+    smvbdd: &mut SmvBdd<BM>,
+    fsmbdd: &FsmBdd<BM>,
+    smv: &Smv,
+    spec: usize,
+    args: &Args,
+) -> FsmBdd<BM>
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    let mut fsmbdd = fsmbdd.clone();
     let ltl = if args.generalize_automata {
-        ltl_to_automata_preprocess(&smv, !smv.ltlspecs[0].clone())
+        ltl_to_automata_preprocess(smv, !smv.ltlspecs[spec].clone())
     } else {
         fsmbdd.justice.clear();
-        get_ltl(&smv, &[], args.flatten_define)
+        get_ltl(smv, spec, &[])
     };
+    let ltl = smvbdd.define_propositional(&ltl);
     let ltl_fsmbdd =
-        BuchiAutomata::from_ltl(ltl, &manager, &smvbdd.symbols, &smvbdd.defines).to_fsmbdd();
+        BuchiAutomata::from_ltl(ltl, &fsmbdd.manager, &smvbdd.symbols, &smvbdd.defines)
+            .simplify(&fsmbdd.invariants)
+            .to_fsmbdd();
     let product = fsmbdd.product(&ltl_fsmbdd);
-    dbg!(product.justice.len());
-    println!("traditional smc begin");
-    let start = Instant::now();
-    let forward = product.reachable_from_init();
-    let fair_cycle = lace_run(|_| product.fair_cycle_with_constrain(&forward));
-    ((fair_cycle & forward).is_constant(false), start.elapsed())
+    if !args.export_product.is_empty() {
+        Aig::from_fsmbdd(&product)
+            .write_file(indexed_file(&args.export_product, spec))
+            .unwrap();
+    }
+    product
+}
+
+/// Checks every LTL spec of `smv` on its product, forward reachability of each product stays
+/// inside the reachable states of the model shared between them.
+pub fn check<BM: BddManager>(manager: BM, smv: Smv, args: Args) -> (bool, Duration)
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    let (mut smvbdd, fsmbdd) = model_fsmbdd(&manager, &smv, &args);
+    let model_reach = shared_model_reach(&smv, &fsmbdd, &args);
+    let mut res = true;
+    let mut time = Duration::ZERO;
+    for spec in 0..smv.ltlspecs.len() {
+        let product = ltl_product(&mut smvbdd, &fsmbdd, &smv, spec, &args);
+        dbg!(product.justice.len());
+        println!("traditional smc begin");
+        let start = Instant::now();
+        let forward =
+            lace_run(|_| product.reachable_with_constrain(&product.init, true, true, &model_reach));
+        let fair_cycle =
+            lace_run(|_| product.fair_cycle_with_algorithm(&forward, args.fair_algorithm.into()));
+        let spec_res = (fair_cycle & forward).is_constant(false);
+        time += start.elapsed();
+        println!("ltlspec {}: {}", spec, spec_res);
+        res &= spec_res;
+    }
+    (res, time)
+}
+
+#[cfg(all(test, feature = "sylvan", feature = "peabody"))]
+mod tests {
+    use super::model_fsmbdd;
+    use crate::command::Args;
+    use bdds::{Bdd, BddManager};
+    use clap::Parser;
+    use peabody::Peabody;
+    use smv::Smv;
+    use sylvan::Sylvan;
+
+    /// Three bits shifted in turn by the process the inputs pick, the last one is fed back
+    /// inverted.
+    const RING: &str = "MODULE main
+IVAR
+s0 : boolean;
+s1 : boolean;
+VAR
+b0 : boolean;
+b1 : boolean;
+b2 : boolean;
+INIT
+!b0 & !b1 & !b2
+TRANS
+next(b0) <-> case !s0 & !s1 : !b2; TRUE : b0; esac
+TRANS
+next(b1) <-> case s0 & !s1 : b0; TRUE : b1; esac
+TRANS
+next(b2) <-> case !s0 & s1 : b1; TRUE : b2; esac
+";
+
+    #[test]
+    fn test_backends_agree() {
+        let smv = Smv::parse(RING);
+        let args = Args::parse_from(["ppsmc"]);
+        let peabody = <Peabody as BddManager>::new();
+        let (_, fsmbdd) = model_fsmbdd(&peabody, &smv, &args);
+        let reach = fsmbdd.reachable_from_init();
+        let sylvan = <Sylvan as BddManager>::new();
+        let (_, fsmbdd) = model_fsmbdd(&sylvan, &smv, &args);
+        let sylvan_reach = Bdd::export(&fsmbdd.reachable_from_init());
+        assert!(!reach.is_constant(true) && !reach.is_constant(false));
+        assert_eq!(BddManager::import(&peabody, &sylvan_reach), reach);
+    }
 }
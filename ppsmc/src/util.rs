@@ -1,4 +1,4 @@
-use smv::{Expr, Prefix};
+use smv::{CaseExpr, Expr, Prefix};
 
 fn trans_expr_to_ltl_rec(expr: &Expr) -> Expr {
     match expr {
@@ -10,7 +10,13 @@ fn trans_expr_to_ltl_rec(expr: &Expr) -> Expr {
         },
         Expr::Ident(_) => expr.clone(),
         Expr::LitExpr(_) => expr.clone(),
-        Expr::CaseExpr(_) => todo!(),
+        Expr::CaseExpr(case_expr) => Expr::CaseExpr(CaseExpr {
+            branchs: case_expr
+                .branchs
+                .iter()
+                .map(|(cond, res)| (trans_expr_to_ltl_rec(cond), trans_expr_to_ltl_rec(res)))
+                .collect(),
+        }),
         Expr::InfixExpr(infix, left, right) => Expr::InfixExpr(
             infix.clone(),
             Box::new(trans_expr_to_ltl_rec(left)),
@@ -1,4 +1,4 @@
-use crate::{ast::Expr, Define, Smv};
+use crate::{Expr, ExprDag, ExprId, Infix, Prefix, Smv};
 use bdds::{Bdd, BddManager};
 use fsmbdd::{FsmBdd, TransBddMethod};
 use std::{
@@ -24,15 +24,13 @@ where
     pub init: BM::Bdd,
     pub invariants: BM::Bdd,
     pub justice: Vec<BM::Bdd>,
+    exprs: ExprToBdd<BM>,
 }
 
-pub fn expr_to_bdd<BM: BddManager>(
-    manager: &BM,
-    symbols: &HashMap<String, usize>,
-    defines: &HashMap<String, Define>,
-    defines_cache: &mut HashMap<String, BM::Bdd>,
-    expr: &Expr,
-) -> BM::Bdd
+/// Converts expressions of a model to BDDs through one `ExprDag`. The defines are expanded and
+/// the BDDs cached by node, so a subexpression shared between defines is converted once.
+#[derive(Clone)]
+struct ExprToBdd<BM: BddManager>
 where
     for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
         + BitAnd<BM::Bdd, Output = BM::Bdd>
@@ -42,69 +40,79 @@ where
         + BitXor<BM::Bdd, Output = BM::Bdd>
         + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
 {
-    let ans = match expr {
-        Expr::Ident(ident) => {
-            if let Some(define) = defines.get(ident) {
-                return if let Some(bdd) = defines_cache.get(ident) {
-                    bdd.clone()
-                } else {
-                    let bdd = expr_to_bdd(manager, symbols, defines, defines_cache, &define.expr);
-                    defines_cache.insert(define.ident.clone(), bdd.clone());
-                    bdd
-                };
-            }
-            manager.ith_var(symbols[ident])
-        }
-        Expr::LitExpr(lit) => manager.constant(*lit),
-        Expr::PrefixExpr(op, sub_expr) => {
-            let expr_bdd = expr_to_bdd(manager, symbols, defines, defines_cache, sub_expr);
-            match op {
-                crate::ast::Prefix::Not => !expr_bdd,
-                crate::ast::Prefix::Next => expr_bdd.next_state(),
-                _ => todo!(),
-            }
-        }
-        Expr::InfixExpr(op, left, right) => {
-            let left_bdd = expr_to_bdd(manager, symbols, defines, defines_cache, left);
-            let right_bdd = expr_to_bdd(manager, symbols, defines, defines_cache, right);
-            match op {
-                crate::ast::Infix::And => left_bdd & right_bdd,
-                crate::ast::Infix::Or => left_bdd | right_bdd,
-                crate::ast::Infix::Xor => left_bdd ^ right_bdd,
-                crate::ast::Infix::Imply => !left_bdd | right_bdd,
-                crate::ast::Infix::Iff => !(left_bdd ^ right_bdd),
-                _ => todo!(),
-            }
+    dag: ExprDag,
+    defines: HashMap<String, ExprId>,
+    expanded: HashMap<ExprId, ExprId>,
+    vars: HashMap<String, BM::Bdd>,
+    cache: HashMap<ExprId, BM::Bdd>,
+}
+
+impl<BM: BddManager> ExprToBdd<BM>
+where
+    for<'a, 'b> &'a BM::Bdd: Not<Output = BM::Bdd>
+        + BitAnd<BM::Bdd, Output = BM::Bdd>
+        + BitAnd<&'b BM::Bdd, Output = BM::Bdd>
+        + BitOr<BM::Bdd, Output = BM::Bdd>
+        + BitOr<&'b BM::Bdd, Output = BM::Bdd>
+        + BitXor<BM::Bdd, Output = BM::Bdd>
+        + BitXor<&'b BM::Bdd, Output = BM::Bdd>,
+{
+    fn new(manager: &BM, smv: &Smv, symbols: &HashMap<String, usize>) -> Self {
+        let mut dag = ExprDag::new();
+        let defines = smv.intern_defines(&mut dag);
+        let vars = symbols
+            .iter()
+            .map(|(ident, var)| (ident.clone(), manager.ith_var(*var)))
+            .collect();
+        Self {
+            dag,
+            defines,
+            expanded: HashMap::new(),
+            vars,
+            cache: HashMap::new(),
         }
-        Expr::CaseExpr(case_expr) => {
-            let mut ans = expr_to_bdd(
-                manager,
-                symbols,
-                defines,
-                defines_cache,
-                &case_expr.branchs.last().unwrap().1,
-            );
-            for i in (0..case_expr.branchs.len() - 1).rev() {
-                let cond = expr_to_bdd(
-                    manager,
-                    symbols,
-                    defines,
-                    defines_cache,
-                    &case_expr.branchs[i].0,
-                );
-                let res = expr_to_bdd(
-                    manager,
-                    symbols,
-                    defines,
-                    defines_cache,
-                    &case_expr.branchs[i].1,
-                );
-                ans = cond.if_then_else(&res, &ans);
-            }
-            ans
+    }
+
+    fn expr_to_bdd(&mut self, manager: &BM, expr: &Expr) -> BM::Bdd {
+        let id = self.dag.insert(expr);
+        let id = self
+            .dag
+            .expand_defines(id, &self.defines, &mut self.expanded);
+        self.dag.to_bdd(id, manager, &self.vars, &mut self.cache)
+    }
+}
+
+fn is_temporal_expr(smv: &Smv, expr: &Expr, cache: &mut HashMap<String, bool>) -> bool {
+    match expr {
+        Expr::Ident(ident) => is_temporal_define(smv, ident, cache),
+        Expr::LitExpr(_) => false,
+        Expr::PrefixExpr(Prefix::Not | Prefix::Next, sub_expr) => {
+            is_temporal_expr(smv, sub_expr, cache)
         }
+        Expr::PrefixExpr(..) => true,
+        Expr::InfixExpr(
+            Infix::And | Infix::Or | Infix::Xor | Infix::Imply | Infix::Iff,
+            left,
+            right,
+        ) => is_temporal_expr(smv, left, cache) || is_temporal_expr(smv, right, cache),
+        Expr::InfixExpr(..) => true,
+        Expr::CaseExpr(case_expr) => case_expr.branchs.iter().any(|(cond, res)| {
+            is_temporal_expr(smv, cond, cache) || is_temporal_expr(smv, res, cache)
+        }),
+    }
+}
+
+/// Whether the define `ident` has LTL operators, through the defines it refers to.
+fn is_temporal_define(smv: &Smv, ident: &str, cache: &mut HashMap<String, bool>) -> bool {
+    let Some(define) = smv.defines.get(ident) else {
+        return false;
     };
-    ans
+    if let Some(temporal) = cache.get(ident) {
+        return *temporal;
+    }
+    let temporal = is_temporal_expr(smv, &define.expr, cache);
+    cache.insert(ident.to_string(), temporal);
+    temporal
 }
 
 impl<BM: BddManager> SmvBdd<BM>
@@ -125,50 +133,35 @@ where
             assert!(symbols.insert(smv.vars[i].ident.clone(), current).is_none());
             manager.ith_var(next);
         }
+        let mut exprs = ExprToBdd::new(manager, smv, &symbols);
         let mut defines = HashMap::new();
-        let smv_define = smv.defines.clone();
-        for define in smv_define {
-            let bdd = expr_to_bdd(
-                manager,
-                &symbols,
-                &smv.defines,
-                &mut defines,
-                &define.1.expr,
-            );
-            defines.insert(define.0, bdd);
+        let mut temporal = HashMap::new();
+        for ident in smv.defines.keys() {
+            // defines with LTL operators only appear in specs and are expanded there
+            if is_temporal_define(smv, ident, &mut temporal) {
+                continue;
+            }
+            let bdd = exprs.expr_to_bdd(manager, &Expr::Ident(ident.clone()));
+            defines.insert(ident.clone(), bdd);
         }
 
         let mut invariants = manager.constant(true);
-        for i in 0..smv.invariants.len() {
-            let expr_ddnode = expr_to_bdd(
-                manager,
-                &symbols,
-                &smv.defines,
-                &mut defines,
-                &smv.invariants[i],
-            );
-            invariants &= expr_ddnode;
-        }
-        let mut trans = vec![];
-        for i in 0..smv.trans.len() {
-            trans.push(expr_to_bdd(
-                manager,
-                &symbols,
-                &smv.defines,
-                &mut defines,
-                &smv.trans[i],
-            ))
+        for invariant in smv.invariants.iter() {
+            invariants &= exprs.expr_to_bdd(manager, invariant);
         }
+        let trans = smv
+            .trans
+            .iter()
+            .map(|trans| exprs.expr_to_bdd(manager, trans))
+            .collect();
         let mut init = manager.constant(true);
-        for i in 0..smv.inits.len() {
-            let expr_ddnode =
-                expr_to_bdd(manager, &symbols, &smv.defines, &mut defines, &smv.inits[i]);
-            init &= expr_ddnode;
+        for expr in smv.inits.iter() {
+            init &= exprs.expr_to_bdd(manager, expr);
         }
         let justice = smv
             .fairness
             .iter()
-            .map(|fair| expr_to_bdd(manager, &symbols, &smv.defines, &mut defines, fair))
+            .map(|fair| exprs.expr_to_bdd(manager, fair))
             .collect();
         Self {
             defines,
@@ -178,11 +171,22 @@ where
             init,
             invariants,
             justice,
+            exprs,
         }
     }
 
-    pub fn to_fsmbdd(&self, method: TransBddMethod) -> FsmBdd<BM> {
-        let trans = fsmbdd::Trans::new(&self.manager, self.trans.clone(), method);
+    /// BDD of a state expression over the variables and defines of the model.
+    pub fn expr_to_bdd(&mut self, expr: &Expr) -> BM::Bdd {
+        self.exprs.expr_to_bdd(&self.manager, expr)
+    }
+
+    pub fn to_fsmbdd(&self, method: TransBddMethod, cluster_threshold: usize) -> FsmBdd<BM> {
+        let trans = fsmbdd::Trans::new_with_threshold(
+            &self.manager,
+            self.trans.clone(),
+            method,
+            cluster_threshold,
+        );
         FsmBdd {
             symbols: self.symbols.clone(),
             manager: self.manager.clone(),
@@ -192,4 +196,198 @@ where
             justice: self.justice.clone(),
         }
     }
+
+    /// Replaces every maximal propositional subformula of `ltl` other than a literal by a fresh
+    /// define `ltl_prop<i>` holding its BDD, so the automaton sees it as one proposition.
+    /// Subformulas with the same BDD or its negation share a define.
+    pub fn define_propositional(&mut self, ltl: &Expr) -> Expr {
+        let mut props = Vec::new();
+        self.define_propositional_rec(ltl, &mut props)
+    }
+
+    fn define_propositional_rec(&mut self, ltl: &Expr, props: &mut Vec<(String, BM::Bdd)>) -> Expr {
+        let literal = match ltl {
+            Expr::Ident(_) | Expr::LitExpr(_) => true,
+            Expr::PrefixExpr(Prefix::Not, sub_expr) => matches!(**sub_expr, Expr::Ident(_)),
+            _ => false,
+        };
+        if literal {
+            return ltl.clone();
+        }
+        if ltl.is_propositional() {
+            let bdd = self.expr_to_bdd(ltl);
+            for lit in [true, false] {
+                if bdd.is_constant(lit) {
+                    return Expr::LitExpr(lit);
+                }
+            }
+            for (ident, prop) in props.iter() {
+                if *prop == bdd {
+                    return Expr::Ident(ident.clone());
+                }
+                if *prop == !&bdd {
+                    return !Expr::Ident(ident.clone());
+                }
+            }
+            let ident = (props.len()..)
+                .map(|i| format!("ltl_prop{}", i))
+                .find(|ident| {
+                    !self.defines.contains_key(ident) && !self.symbols.contains_key(ident)
+                })
+                .unwrap();
+            self.defines.insert(ident.clone(), bdd.clone());
+            props.push((ident.clone(), bdd));
+            return Expr::Ident(ident);
+        }
+        match ltl {
+            Expr::PrefixExpr(op, sub_expr) => Expr::PrefixExpr(
+                op.clone(),
+                Box::new(self.define_propositional_rec(sub_expr, props)),
+            ),
+            Expr::InfixExpr(op, left, right) => Expr::InfixExpr(
+                op.clone(),
+                Box::new(self.define_propositional_rec(left, props)),
+                Box::new(self.define_propositional_rec(right, props)),
+            ),
+            _ => ltl.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SmvBdd;
+    use crate::{Dialect, Expr, Prefix, Smv};
+    use peabody::Peabody;
+    use std::{fs::read_dir, path::Path};
+
+    /// Maximal propositional subformulas of `expr`.
+    fn propositional(expr: &Expr, res: &mut Vec<Expr>) {
+        if expr.is_propositional() {
+            res.push(expr.clone());
+            return;
+        }
+        match expr {
+            Expr::PrefixExpr(_, sub_expr) => propositional(sub_expr, res),
+            Expr::InfixExpr(_, left, right) => {
+                propositional(left, res);
+                propositional(right, res);
+            }
+            Expr::CaseExpr(case_expr) => {
+                for (cond, branch) in case_expr.branchs.iter() {
+                    propositional(cond, res);
+                    propositional(branch, res);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// The defines and the propositional parts of the specs of every benchmark give the same
+    /// BDD directly and through their ltl2ba text read back as automaton labels.
+    #[test]
+    fn test_benchmark_specs() {
+        let benchmark = Path::new(env!("CARGO_MANIFEST_DIR")).join("../benchmark");
+        let mut files: Vec<_> = read_dir(benchmark)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        files.sort();
+        for file in files {
+            let smv = Smv::from_file(&file).unwrap();
+            let manager = Peabody::new();
+            let mut smvbdd = SmvBdd::new(&manager, &smv);
+            let mut exprs: Vec<Expr> = smv
+                .defines
+                .values()
+                .filter(|define| smvbdd.defines.contains_key(&define.ident))
+                .map(|define| define.expr.clone())
+                .collect();
+            let mut atoms = Vec::new();
+            for spec in smv.ltlspecs.iter() {
+                let ltl = smv.flatten_to_propositional_define(spec).simplify_ltl();
+                propositional(&ltl, &mut exprs);
+                let ltl = smvbdd.define_propositional(&ltl);
+                let mut props = Vec::new();
+                propositional(&ltl, &mut props);
+                for prop in props {
+                    let atom = match &prop {
+                        Expr::PrefixExpr(Prefix::Not, sub_expr) => sub_expr,
+                        _ => &prop,
+                    };
+                    match atom {
+                        Expr::Ident(ident) => atoms.push(ident.clone()),
+                        Expr::LitExpr(_) => (),
+                        _ => panic!("{} is not a literal", prop),
+                    }
+                }
+            }
+            let mut labels = smvbdd.defines.clone();
+            for (ident, var) in smvbdd.symbols.iter() {
+                labels.insert(ident.clone(), manager.ith_var(*var));
+            }
+            assert!(atoms.iter().all(|atom| labels.contains_key(atom)));
+            for expr in exprs {
+                let bdd = smvbdd.expr_to_bdd(&expr);
+                for dialect in [Dialect::Spot, Dialect::Ltl2ba] {
+                    let text = expr.display(dialect).to_string();
+                    let label = Expr::parse(&text, dialect).to_bdd(&manager, &labels);
+                    assert!(label == bdd, "{}: {}", file.display(), text);
+                }
+            }
+        }
+    }
+
+    fn size(expr: &Expr) -> usize {
+        match expr {
+            Expr::Ident(_) | Expr::LitExpr(_) => 1,
+            Expr::PrefixExpr(_, sub_expr) => 1 + size(sub_expr),
+            Expr::InfixExpr(_, left, right) => 1 + size(left) + size(right),
+            Expr::CaseExpr(case_expr) => case_expr
+                .branchs
+                .iter()
+                .map(|(cond, branch)| 1 + size(cond) + size(branch))
+                .sum(),
+        }
+    }
+
+    #[test]
+    fn test_flatten_shared_defines() {
+        // every define refers to the previous one twice, its tree doubles at each step
+        let mut model =
+            "MODULE main\nVAR\na : boolean;\nb : boolean;\nDEFINE\nd0 := a & !b;\n".to_string();
+        for i in 1..64 {
+            model += &format!("d{} := case d{1} : b; TRUE : !d{1}; esac;\n", i, i - 1);
+        }
+        model += "INIT\nd63\nLTLSPEC\nG (d62 -> X d63)\n";
+        let smv = Smv::parse(&model);
+        let flat = smv.flatten_defines();
+        let total: usize = flat
+            .inits
+            .iter()
+            .chain(flat.ltlspecs.iter())
+            .map(size)
+            .sum::<usize>()
+            + flat
+                .defines
+                .values()
+                .map(|define| size(&define.expr))
+                .sum::<usize>();
+        assert!(total < 64 * 16);
+        assert!(!flat.defines.contains_key("d63"));
+        let manager = Peabody::new();
+        let mut smvbdd = SmvBdd::new(&manager, &smv);
+        let mut flat_smvbdd = SmvBdd::new(&manager, &flat);
+        assert_eq!(flat_smvbdd.init, smvbdd.init);
+        let Expr::PrefixExpr(Prefix::LtlGlobally, flat_spec) = &flat.ltlspecs[0] else {
+            panic!("not a G spec");
+        };
+        let Expr::InfixExpr(_, flat_d62, _) = &**flat_spec else {
+            panic!("not an implication");
+        };
+        assert_eq!(
+            flat_smvbdd.expr_to_bdd(flat_d62),
+            smvbdd.expr_to_bdd(&Expr::Ident("d62".to_string()))
+        );
+    }
 }
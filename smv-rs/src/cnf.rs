@@ -0,0 +1,329 @@
+use crate::{Expr, Infix, Prefix, Smv};
+use logic_form::{Clause, Cnf, Lit, Var};
+use std::{collections::HashMap, mem::take};
+
+/// Unrolls an smv model into CNF. Frame `k` holds a fresh variable for every model variable,
+/// expressions are Tseitin encoded on demand and the clauses are collected until `take_cnf`.
+pub struct SmvCnf {
+    smv: Smv,
+    num_var: usize,
+    cnf: Cnf,
+    frames: Vec<HashMap<String, Lit>>,
+    defines: HashMap<(String, usize), Lit>,
+}
+
+impl SmvCnf {
+    pub fn new(smv: &Smv) -> Self {
+        let mut res = Self {
+            smv: smv.clone(),
+            num_var: 1,
+            cnf: Cnf::new(),
+            frames: Vec::new(),
+            defines: HashMap::new(),
+        };
+        res.add_frame();
+        res
+    }
+
+    pub fn new_var(&mut self) -> Lit {
+        let var = Var::from(self.num_var);
+        self.num_var += 1;
+        Lit::new(var, false)
+    }
+
+    pub fn num_var(&self) -> usize {
+        self.num_var
+    }
+
+    pub fn num_frame(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Adds the next frame with its invariants.
+    pub fn add_frame(&mut self) {
+        let vars: Vec<String> = self.smv.vars.iter().map(|var| var.ident.clone()).collect();
+        let frame = vars
+            .into_iter()
+            .map(|ident| (ident, self.new_var()))
+            .collect();
+        self.frames.push(frame);
+        let step = self.frames.len() - 1;
+        for invariant in self.smv.invariants.clone() {
+            let lit = self.encode(&invariant, step);
+            self.cnf.add_clause(Clause::from([lit]));
+        }
+    }
+
+    /// The transition from `step` to `step + 1`.
+    pub fn trans(&mut self, step: usize) -> Lit {
+        let trans: Vec<Lit> = self
+            .smv
+            .trans
+            .clone()
+            .iter()
+            .map(|trans| self.encode(trans, step))
+            .collect();
+        self.and(&trans)
+    }
+
+    /// Adds the next frame and asserts the transition into it.
+    pub fn unroll(&mut self) {
+        let step = self.frames.len() - 1;
+        self.add_frame();
+        let trans = self.trans(step);
+        self.cnf.add_clause(Clause::from([trans]));
+    }
+
+    pub fn init(&mut self) -> Lit {
+        let inits: Vec<Lit> = self
+            .smv
+            .inits
+            .clone()
+            .iter()
+            .map(|init| self.encode(init, 0))
+            .collect();
+        self.and(&inits)
+    }
+
+    /// Literals of the model variables at `step`, in declaration order.
+    pub fn state(&self, step: usize) -> Vec<Lit> {
+        self.smv
+            .vars
+            .iter()
+            .map(|var| self.frames[step][&var.ident])
+            .collect()
+    }
+
+    pub fn state_equal(&mut self, x: usize, y: usize) -> Lit {
+        let eqs: Vec<Lit> = self
+            .state(x)
+            .into_iter()
+            .zip(self.state(y))
+            .map(|(a, b)| !self.xor(a, b))
+            .collect();
+        self.and(&eqs)
+    }
+
+    pub fn take_cnf(&mut self) -> Cnf {
+        take(&mut self.cnf)
+    }
+
+    pub fn and(&mut self, lits: &[Lit]) -> Lit {
+        let mut res = Vec::new();
+        for lit in lits.iter() {
+            if *lit == Lit::constant_lit(false) || res.contains(&!*lit) {
+                return Lit::constant_lit(false);
+            }
+            if *lit != Lit::constant_lit(true) && !res.contains(lit) {
+                res.push(*lit);
+            }
+        }
+        match res.len() {
+            0 => Lit::constant_lit(true),
+            1 => res[0],
+            _ => {
+                let out = self.new_var();
+                let mut clause = Clause::from([out]);
+                for lit in res.iter() {
+                    self.cnf.add_clause(Clause::from([!out, *lit]));
+                    clause.push(!*lit);
+                }
+                self.cnf.add_clause(clause);
+                out
+            }
+        }
+    }
+
+    pub fn or(&mut self, lits: &[Lit]) -> Lit {
+        let lits: Vec<Lit> = lits.iter().map(|lit| !*lit).collect();
+        !self.and(&lits)
+    }
+
+    pub fn xor(&mut self, x: Lit, y: Lit) -> Lit {
+        if x == Lit::constant_lit(false) {
+            return y;
+        }
+        if x == Lit::constant_lit(true) {
+            return !y;
+        }
+        if y == Lit::constant_lit(false) || y == Lit::constant_lit(true) {
+            return self.xor(y, x);
+        }
+        if x == y {
+            return Lit::constant_lit(false);
+        }
+        if x == !y {
+            return Lit::constant_lit(true);
+        }
+        let out = self.new_var();
+        self.cnf.add_clause(Clause::from([!out, x, y]));
+        self.cnf.add_clause(Clause::from([!out, !x, !y]));
+        self.cnf.add_clause(Clause::from([out, !x, y]));
+        self.cnf.add_clause(Clause::from([out, x, !y]));
+        out
+    }
+
+    pub fn ite(&mut self, cond: Lit, then: Lit, els: Lit) -> Lit {
+        let then = self.and(&[cond, then]);
+        let els = self.and(&[!cond, els]);
+        self.or(&[then, els])
+    }
+
+    /// Encodes the state expression `expr` at `step`, `next` refers to `step + 1`.
+    pub fn encode(&mut self, expr: &Expr, step: usize) -> Lit {
+        match expr {
+            Expr::Ident(ident) => {
+                if let Some(lit) = self.defines.get(&(ident.clone(), step)) {
+                    return *lit;
+                }
+                if let Some(define) = self.smv.defines.get(ident) {
+                    let define = define.expr.clone();
+                    let lit = self.encode(&define, step);
+                    self.defines.insert((ident.clone(), step), lit);
+                    return lit;
+                }
+                match self.frames[step].get(ident) {
+                    Some(lit) => *lit,
+                    None => panic!("unknown identifier {}", ident),
+                }
+            }
+            Expr::LitExpr(lit) => Lit::constant_lit(*lit),
+            Expr::PrefixExpr(op, sub_expr) => match op {
+                Prefix::Not => !self.encode(sub_expr, step),
+                Prefix::Next => {
+                    assert!(step + 1 < self.frames.len(), "next beyond the last frame");
+                    self.encode(sub_expr, step + 1)
+                }
+                _ => panic!("temporal operator {} in a state expression", op),
+            },
+            Expr::InfixExpr(op, left, right) => {
+                let left = self.encode(left, step);
+                let right = self.encode(right, step);
+                match op {
+                    Infix::And => self.and(&[left, right]),
+                    Infix::Or => self.or(&[left, right]),
+                    Infix::Xor => self.xor(left, right),
+                    Infix::Imply => self.or(&[!left, right]),
+                    Infix::Iff => !self.xor(left, right),
+                    _ => panic!("temporal operator {} in a state expression", op),
+                }
+            }
+            Expr::CaseExpr(case_expr) => {
+                // false if no branch applies, like the BDD encoding
+                let mut ans = Lit::constant_lit(false);
+                for (cond, res) in case_expr.branchs.iter().rev() {
+                    let cond = self.encode(cond, step);
+                    let res = self.encode(res, step);
+                    ans = self.ite(cond, res, ans);
+                }
+                ans
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SmvCnf;
+    use crate::{Dialect, Expr, Smv};
+    use cdcl::Solver;
+    use logic_form::Lit;
+
+    /// Counts 0, 1, 2 and back to 0 in `b1 b0`, 3 is unreachable.
+    const COUNTER: &str = "MODULE main
+VAR
+b0 : boolean;
+b1 : boolean;
+INIT
+!b0 & !b1
+TRANS
+next(b0) <-> (!b0 & !b1)
+TRANS
+next(b1) <-> (b0 & !b1)
+";
+
+    fn unrolled(steps: usize) -> (SmvCnf, Solver) {
+        let mut unroller = SmvCnf::new(&Smv::parse(COUNTER));
+        let init = unroller.init();
+        for _ in 0..steps {
+            unroller.unroll();
+        }
+        let mut solver = Solver::new();
+        solver.add_clause(&[init]);
+        solver.add_cnf(&unroller.take_cnf());
+        (unroller, solver)
+    }
+
+    #[test]
+    fn test_unroll() {
+        let (unroller, mut solver) = unrolled(3);
+        assert_eq!(unroller.num_frame(), 4);
+        assert!(solver.solve(&[]));
+        let values: Vec<Vec<Option<bool>>> = (0..4)
+            .map(|step| {
+                unroller
+                    .state(step)
+                    .into_iter()
+                    .map(|lit| solver.lit_value(lit))
+                    .collect()
+            })
+            .collect();
+        let expected = [[false, false], [true, false], [false, true], [false, false]];
+        for (values, expected) in values.iter().zip(expected) {
+            assert_eq!(*values, expected.map(Some).to_vec());
+        }
+        for step in 0..4 {
+            let state = unroller.state(step);
+            assert!(!solver.solve(&[state[0], state[1]]));
+        }
+    }
+
+    #[test]
+    fn test_state_equal() {
+        let (mut unroller, mut solver) = unrolled(3);
+        let period = unroller.state_equal(3, 0);
+        let shorter = unroller.state_equal(2, 0);
+        solver.add_cnf(&unroller.take_cnf());
+        assert!(solver.solve(&[period]));
+        assert!(!solver.solve(&[shorter]));
+    }
+
+    #[test]
+    fn test_case_without_default() {
+        let mut unroller = SmvCnf::new(&Smv::parse(COUNTER));
+        let b0 = unroller.state(0)[0];
+        let b1 = unroller.state(0)[1];
+        let case = unroller.encode(
+            &Expr::parse("case b0 : b1; !b0 & b1 : TRUE; esac", Dialect::NuSmv),
+            0,
+        );
+        let mut solver = Solver::new();
+        solver.add_cnf(&unroller.take_cnf());
+        // no branch applies
+        assert!(!solver.solve(&[case, !b0, !b1]));
+        assert!(solver.solve(&[case, !b0, b1]));
+    }
+
+    #[test]
+    fn test_encode() {
+        let mut unroller = SmvCnf::new(&Smv::parse(COUNTER));
+        let b0 = unroller.state(0)[0];
+        let b1 = unroller.state(0)[1];
+        let t = Lit::constant_lit(true);
+        let f = Lit::constant_lit(false);
+        assert_eq!(unroller.and(&[b0, f]), f);
+        assert_eq!(unroller.and(&[b0, !b0]), f);
+        assert_eq!(unroller.and(&[b0, t, b0]), b0);
+        assert_eq!(unroller.or(&[b0, t]), t);
+        assert_eq!(unroller.xor(b0, b0), f);
+        assert_eq!(unroller.xor(t, b0), !b0);
+        let ite = unroller.ite(b0, b1, !b1);
+        let xor = unroller.xor(b0, b1);
+        let mut solver = Solver::new();
+        solver.add_cnf(&unroller.take_cnf());
+        // `ite(b0, b1, !b1)` is `b0 <-> b1`
+        assert!(!solver.solve(&[ite, xor]));
+        assert!(!solver.solve(&[!ite, !xor]));
+        assert!(solver.solve(&[ite, b0, b1]));
+    }
+}
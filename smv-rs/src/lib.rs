@@ -1,15 +1,13 @@
-mod ast;
 pub mod bdd;
-mod lexer;
+pub mod cnf;
 mod parser;
-mod token;
 
-pub use ast::*;
+pub use expr::{CaseExpr, Dialect, Expr, ExprDag, ExprId, ExprNode, Infix, Prefix};
 
-use crate::{parser::parse_tokens, token::Tokens};
-use lexer::lex_tokens;
+use crate::parser::parse_tokens;
+use expr::{lexer::lex_tokens, token::Tokens};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashMap,
     fs::read_to_string,
     io,
     mem::take,
@@ -40,53 +38,12 @@ pub struct Smv {
 }
 
 impl Smv {
-    fn flatten_expr(&mut self, expr: Expr, flattened: &mut HashSet<String>) -> Expr {
-        match expr {
-            Expr::LitExpr(_) => expr,
-            Expr::Ident(ident) => {
-                if let Some(define) = self.defines.get(&ident) {
-                    if !flattened.contains(&ident) {
-                        let mut define = define.clone();
-                        define.expr = self.flatten_expr(define.expr, flattened);
-                        self.defines.insert(ident.clone(), define);
-                        flattened.insert(ident.clone());
-                        return self.defines.get(&ident).unwrap().expr.clone();
-                    } else {
-                        return define.expr.clone();
-                    }
-                }
-                for latch in self.vars.iter() {
-                    if latch.ident == ident {
-                        return Expr::Ident(ident);
-                    }
-                }
-                panic!()
-            }
-            Expr::PrefixExpr(op, sub_expr) => {
-                Expr::PrefixExpr(op, Box::new(self.flatten_expr(*sub_expr, flattened)))
-            }
-            Expr::InfixExpr(op, left, right) => Expr::InfixExpr(
-                op,
-                Box::new(self.flatten_expr(*left, flattened)),
-                Box::new(self.flatten_expr(*right, flattened)),
-            ),
-            Expr::CaseExpr(case_expr) => {
-                // case_expr.branchs = case_expr
-                //     .branchs
-                //     .into_iter()
-                //     .map(|(x, y)| (self.flatten_expr(x), self.flatten_expr(y)))
-                //     .collect();
-                // Expr::CaseExpr(case_expr)
-                let mut ans =
-                    self.flatten_expr(case_expr.branchs.last().unwrap().1.clone(), flattened);
-                for i in (0..case_expr.branchs.len() - 1).rev() {
-                    let cond = self.flatten_expr(case_expr.branchs[i].0.clone(), flattened);
-                    let res = self.flatten_expr(case_expr.branchs[i].1.clone(), flattened);
-                    ans = (cond.clone() & res) | (!cond & ans);
-                }
-                ans
-            }
-        }
+    /// Interns the expression of every define.
+    pub fn intern_defines(&self, dag: &mut ExprDag) -> HashMap<String, ExprId> {
+        self.defines
+            .iter()
+            .map(|(ident, define)| (ident.clone(), dag.insert(&define.expr)))
+            .collect()
     }
 
     fn dedup(&mut self) {
@@ -100,8 +57,8 @@ impl Smv {
 }
 
 impl Smv {
-    fn parse(input: &str) -> Self {
-        let tokens = lex_tokens(input).unwrap();
+    pub fn parse(input: &str) -> Self {
+        let tokens = lex_tokens(input, Dialect::NuSmv).unwrap();
         let tokens = Tokens::new(&tokens);
         let mut smv = parse_tokens(tokens).unwrap();
         smv.dedup();
@@ -113,24 +70,54 @@ impl Smv {
         Ok(Self::parse(&s))
     }
 
+    /// Expands the defines and the case expressions of every expression. Both are expanded
+    /// once in an `ExprDag`, whose shared nodes become fresh defines `flat<i>` in place of the
+    /// original ones, so the result stays as large as the DAG.
     pub fn flatten_defines(&self) -> Self {
         let mut res = self.clone();
-        let mut flattend = HashSet::new();
-        for i in 0..res.inits.len() {
-            res.inits[i] = res.flatten_expr(res.inits[i].clone(), &mut flattend);
-        }
-        for i in 0..res.trans.len() {
-            res.trans[i] = res.flatten_expr(res.trans[i].clone(), &mut flattend);
-        }
-        for i in 0..res.invariants.len() {
-            res.invariants[i] = res.flatten_expr(res.invariants[i].clone(), &mut flattend);
-        }
-        for i in 0..res.fairness.len() {
-            res.fairness[i] = res.flatten_expr(res.fairness[i].clone(), &mut flattend);
-        }
-        for i in 0..res.ltlspecs.len() {
-            res.ltlspecs[i] = res.flatten_expr(res.ltlspecs[i].clone(), &mut flattend);
+        let mut dag = ExprDag::new();
+        let defines = self.intern_defines(&mut dag);
+        let mut expanded = HashMap::new();
+        let mut lowered = HashMap::new();
+        let roots: Vec<ExprId> = res
+            .inits
+            .iter()
+            .chain(res.trans.iter())
+            .chain(res.invariants.iter())
+            .chain(res.fairness.iter())
+            .chain(res.ltlspecs.iter())
+            .map(|expr| {
+                let id = dag.insert(expr);
+                let id = dag.expand_defines(id, &defines, &mut expanded);
+                dag.lower_case(id, &mut lowered)
+            })
+            .collect();
+        let mut fresh = 0..;
+        let (flattened, flat_defines) = dag.to_exprs_with_defines(&roots, |_| {
+            fresh
+                .by_ref()
+                .map(|i| format!("flat{}", i))
+                .find(|ident| {
+                    !self.defines.contains_key(ident)
+                        && !self.vars.iter().any(|var| var.ident == *ident)
+                })
+                .unwrap()
+        });
+        let mut flattened = flattened.into_iter();
+        let exprs = res
+            .inits
+            .iter_mut()
+            .chain(res.trans.iter_mut())
+            .chain(res.invariants.iter_mut())
+            .chain(res.fairness.iter_mut())
+            .chain(res.ltlspecs.iter_mut());
+        for expr in exprs {
+            *expr = flattened.next().unwrap();
         }
+        res.defines = flat_defines
+            .into_iter()
+            .map(|(ident, expr)| (ident.clone(), Define { ident, expr }))
+            .collect();
         res
     }
 
@@ -214,26 +201,10 @@ impl Smv {
     }
 
     pub fn flatten_case(&self, expr: Expr) -> Expr {
-        match expr {
-            Expr::Ident(_) | Expr::LitExpr(_) => expr,
-            Expr::PrefixExpr(op, sub_expr) => {
-                Expr::PrefixExpr(op, Box::new(self.flatten_case(*sub_expr)))
-            }
-            Expr::InfixExpr(op, left, right) => Expr::InfixExpr(
-                op,
-                Box::new(self.flatten_case(*left)),
-                Box::new(self.flatten_case(*right)),
-            ),
-            Expr::CaseExpr(case_expr) => {
-                let mut ans = self.flatten_case(case_expr.branchs.last().unwrap().1.clone());
-                for i in (0..case_expr.branchs.len() - 1).rev() {
-                    let cond = self.flatten_case(case_expr.branchs[i].0.clone());
-                    let res = self.flatten_case(case_expr.branchs[i].1.clone());
-                    ans = (cond.clone() & res) | (!cond & ans);
-                }
-                ans
-            }
-        }
+        let mut dag = ExprDag::new();
+        let id = dag.insert(&expr);
+        let id = dag.lower_case(id, &mut HashMap::new());
+        dag.to_expr(id)
     }
 }
 
@@ -1,5 +1,6 @@
 use crate::Sylvan;
 use std::{
+    collections::HashMap,
     fmt::Debug,
     ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not},
 };
@@ -9,8 +10,8 @@ use sylvan_sys::{
         Sylvan_relnext, Sylvan_relprev, Sylvan_xor,
     },
     mtbdd::{
-        Sylvan_map_add, Sylvan_map_empty, Sylvan_nodecount, Sylvan_protect, Sylvan_support,
-        Sylvan_unprotect,
+        Sylvan_high, Sylvan_low, Sylvan_map_add, Sylvan_map_empty, Sylvan_nodecount,
+        Sylvan_protect, Sylvan_support, Sylvan_unprotect, Sylvan_var,
     },
     *,
 };
@@ -234,6 +235,38 @@ impl Bdd {
         let res = unsafe { Sylvan_relprev(*tran.node, *self.node, SYLVAN_FALSE) };
         Bdd::new(res)
     }
+
+    /// Lists the nodes as `(var, low, high)`, children before parents. References `0` and `1`
+    /// are the constants, reference `i + 2` is the i-th node. Returns the nodes and the root.
+    pub fn export(&self) -> (Vec<(usize, usize, usize)>, usize) {
+        let mut nodes = Vec::new();
+        let mut map = HashMap::from([(MTBDD_FALSE, 0), (MTBDD_TRUE, 1)]);
+        let mut stack = vec![*self.node];
+        while let Some(node) = stack.last().copied() {
+            if map.contains_key(&node) {
+                stack.pop();
+                continue;
+            }
+            let (low, high) = unsafe { (Sylvan_low(node), Sylvan_high(node)) };
+            match (map.get(&low), map.get(&high)) {
+                (Some(l), Some(h)) => {
+                    let var = unsafe { Sylvan_var(node) } as usize;
+                    nodes.push((var, *l, *h));
+                    map.insert(node, nodes.len() + 1);
+                    stack.pop();
+                }
+                (l, h) => {
+                    if l.is_none() {
+                        stack.push(low);
+                    }
+                    if h.is_none() {
+                        stack.push(high);
+                    }
+                }
+            }
+        }
+        (nodes, map[&*self.node])
+    }
 }
 
 impl Bdd {